@@ -0,0 +1,552 @@
+//! The `#[contract]` attribute, which checks a function's Flux `spec` at run time.
+//!
+//! Placed _above_ a `#[spec(...)]` (or `#[sig(...)]`), as in
+//!
+//! ```ignore
+//! #[contract]
+//! #[spec(fn(&Self[@r], k: i32{r.start <= k && k <= r.end}) -> (Self, Self))]
+//! pub fn split_at(&self, k: i32) -> (Range, Range) { ... }
+//! ```
+//!
+//! the attribute reads the refinements of the `spec` and, when the crate is built
+//! with the `runtime-contracts` feature, checks them with `debug_assert!`s:
+//!
+//! - the _preconditions_ are the predicates on the parameters, e.g. `{r.start <= k}`
+//!   or `{v: 0 < v}` or `{RVec<T>[@n] | 0 < n}`, and the `requires` clause;
+//! - the _postconditions_ are the predicates on the result, e.g. `{v: 0 <= v}`,
+//!   or on the payload of an `Option` result, e.g. `Option<u32{v: v <= n}>`,
+//!   which are checked when the result is `Some`.
+//!
+//! The index of a primitive result, e.g. `usize[s + k]`, and the validity index
+//! of an `Option` or `Result`, e.g. `Option<Self[x]>[0 < x]`, which says whether
+//! the result is a `Some` or an `Ok`, are checked too, as long as they only use
+//! arithmetic that means the same in Rust as in the logic, i.e.
+//! no refinement functions, `if`, `/` or `%`, and the function takes no `&mut`
+//! parameters, whose indices may have changed by the time the result is checked.
+//! Other indices, and `ensures` clauses, are not checked. A refinement variable
+//! `@r` stands for the parameter it is bound on, so `r.start` is checked as
+//! `self.start`; when the index is not a field of the parameter, the attribute
+//! takes the value to use instead, e.g. `#[contract(n = self.len())]`.
+//!
+//! Each `&&`-separated clause is checked separately, so that the panic message
+//! names the particular clause that was violated.
+//!
+//! In test builds, the attribute also generates the predicates `<name>_requires`
+//! and `<name>_ensures`, which take the same parameters as the function (and, for
+//! `_ensures`, its result) and return whether _all_ the pre- or postconditions
+//! hold, so that property tests can draw their inputs and check their outputs
+//! against the `spec` itself. As these are extra methods, the attribute can
+//! not be used on the methods of a trait `impl`.
+
+use std::collections::HashMap;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Delimiter, Group, Spacing, TokenStream as Tokens, TokenTree};
+use quote::quote;
+use quote::format_ident;
+use syn::{
+    FnArg, ItemFn, MetaNameValue, Pat, ReturnType, Signature, Token, Type, parse::Parser,
+    parse_quote, punctuated::Punctuated, spanned::Spanned,
+};
+
+#[proc_macro_attribute]
+pub fn contract(args: TokenStream, item: TokenStream) -> TokenStream {
+    match expand(args.into(), item.into()) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// A single clause of a contract: its text in the `spec`, and the Rust condition to check.
+struct Clause {
+    text: Tokens,
+    cond: Tokens,
+}
+
+fn expand(args: Tokens, item: Tokens) -> syn::Result<Tokens> {
+    let item: ItemFn = syn::parse2(item)?;
+
+    let mut bindings = HashMap::new();
+    for arg in Punctuated::<MetaNameValue, Token![,]>::parse_terminated.parse2(args)? {
+        let name = arg.path.require_ident()?.to_string();
+        let value = &arg.value;
+        bindings.insert(name, quote!(#value));
+    }
+
+    let spec = item
+        .attrs
+        .iter()
+        .find(|attr| {
+            attr.path()
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "spec" || s.ident == "sig")
+        })
+        .ok_or_else(|| {
+            syn::Error::new(
+                item.sig.span(),
+                "`#[contract]` must be placed above a `#[spec(...)]`",
+            )
+        })?;
+    let spec_tokens = spec.meta.require_list()?.tokens.clone();
+
+    let rust_names: Vec<Option<Tokens>> = item
+        .sig
+        .inputs
+        .iter()
+        .map(|arg| match arg {
+            FnArg::Receiver(_) => Some(quote!(self)),
+            FnArg::Typed(pat) => match &*pat.pat {
+                Pat::Ident(p) => {
+                    let ident = &p.ident;
+                    Some(quote!(#ident))
+                }
+                _ => None,
+            },
+        })
+        .collect();
+
+    let mutates = item.sig.inputs.iter().any(|arg| match arg {
+        FnArg::Receiver(r) => r.mutability.is_some() && r.reference.is_some(),
+        FnArg::Typed(pat) => matches!(&*pat.ty, Type::Reference(r) if r.mutability.is_some()),
+    });
+    let (pre, post) = parse_spec(spec_tokens, &rust_names, bindings, !mutates)
+        .map_err(|msg| syn::Error::new(spec.span(), msg))?;
+    let predicates = predicates(&item, &pre, &post);
+
+    let pre = pre.iter().map(|Clause { text, cond }| {
+        quote!(debug_assert!(#cond, "precondition violated: `{}`", stringify!(#text));)
+    });
+    let post: Vec<_> = post
+        .iter()
+        .map(|Clause { text, cond }| {
+            quote!(debug_assert!(#cond, "postcondition violated: `{}`", stringify!(#text));)
+        })
+        .collect();
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = &item;
+    let checked = if post.is_empty() {
+        quote!({ #(#pre)* #block })
+    } else {
+        let ret = match &sig.output {
+            ReturnType::Default => quote!(()),
+            ReturnType::Type(_, ty) => quote!(#ty),
+        };
+        // A body that may `return` early is run in a closure, so that the
+        // postconditions are also checked for the early results.
+        let result = if exits_early(quote!(#block)) {
+            quote! {
+                #[allow(clippy::redundant_closure_call)]
+                let __result: #ret = (|| -> #ret #block)();
+            }
+        } else {
+            quote!(let __result: #ret = #block;)
+        };
+        quote!({ #(#pre)* #result #(#post)* __result })
+    };
+
+    Ok(quote! {
+        #[cfg(feature = "runtime-contracts")]
+        #(#attrs)* #vis #sig #checked
+
+        #[cfg(not(feature = "runtime-contracts"))]
+        #(#attrs)* #vis #sig #block
+
+        #predicates
+    })
+}
+
+/// The test-only predicates `<name>_requires` and `<name>_ensures`, the
+/// conjunctions of the pre- and postconditions.
+fn predicates(item: &ItemFn, pre: &[Clause], post: &[Clause]) -> Tokens {
+    let conj = |clauses: &[Clause]| -> Tokens {
+        if clauses.is_empty() {
+            return quote!(true);
+        }
+        let conds = clauses.iter().map(|c| &c.cond);
+        quote!(#((#conds))&&*)
+    };
+    let predicate = |suffix: &str| -> Signature {
+        let mut sig = item.sig.clone();
+        sig.ident = format_ident!("{}_{}", sig.ident, suffix);
+        sig.constness = None;
+        sig.asyncness = None;
+        sig.output = parse_quote!(-> bool);
+        sig
+    };
+    let requires = predicate("requires");
+    let mut ensures = predicate("ensures");
+    let ret: Type = match &item.sig.output {
+        ReturnType::Default => parse_quote!(()),
+        ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+    ensures.inputs.push(parse_quote!(__result: #ret));
+    let (vis, pre, post) = (&item.vis, conj(pre), conj(post));
+    quote! {
+        #[cfg(test)]
+        #[allow(dead_code, unused_variables, unused_mut)]
+        #vis #requires { #pre }
+
+        #[cfg(test)]
+        #[allow(dead_code, unused_variables, unused_mut)]
+        #vis #ensures { #post }
+    }
+}
+
+/// Splits the `spec` into its pre- and postcondition clauses.
+fn parse_spec(
+    spec: Tokens,
+    rust_names: &[Option<Tokens>],
+    mut bindings: HashMap<String, Tokens>,
+    check_index: bool,
+) -> Result<(Vec<Clause>, Vec<Clause>), String> {
+    let toks: Vec<TokenTree> = spec.into_iter().collect();
+    let mut i = 0;
+    if !matches!(toks.first(), Some(TokenTree::Ident(id)) if id == "fn") {
+        return Err("expected a `spec` of the form `fn(...) -> ...`".to_string());
+    }
+    i += 1;
+    if is_punct(toks.get(i), '<') {
+        let mut depth = 0;
+        loop {
+            match toks.get(i) {
+                Some(t) if is_punct(Some(t), '<') => depth += 1,
+                Some(t) if is_punct(Some(t), '>') => depth -= 1,
+                None => return Err("unclosed generics in `spec`".to_string()),
+                _ => {}
+            }
+            i += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+    let params = match toks.get(i) {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => g.stream(),
+        _ => return Err("expected the parameters of the `spec`".to_string()),
+    };
+    i += 1;
+
+    let mut ret = Vec::new();
+    let mut requires = Vec::new();
+    if is_punct(toks.get(i), '-') && is_punct(toks.get(i + 1), '>') {
+        i += 2;
+        while i < toks.len()
+            && !is_keyword(&toks[i], "requires")
+            && !is_keyword(&toks[i], "ensures")
+        {
+            ret.push(toks[i].clone());
+            i += 1;
+        }
+    }
+    if i < toks.len() && is_keyword(&toks[i], "requires") {
+        i += 1;
+        while i < toks.len() && !is_keyword(&toks[i], "ensures") {
+            requires.push(toks[i].clone());
+            i += 1;
+        }
+    }
+
+    // First, bind the spec's names (and refinement variables) to Rust expressions ...
+    let params = split_top_level(params.into_iter().collect(), ',');
+    let mut typed = Vec::new();
+    for (param, rust) in params.into_iter().zip(rust_names.iter().cloned()) {
+        let (name, ty) = match param.as_slice() {
+            [TokenTree::Ident(name), TokenTree::Punct(colon), ty @ ..]
+                if colon.as_char() == ':' && colon.spacing() == Spacing::Alone =>
+            {
+                (Some(name.to_string()), ty.to_vec())
+            }
+            ty => (None, ty.to_vec()),
+        };
+        if let Some(rust) = rust {
+            if let Some(name) = name
+                && name != rust.to_string()
+            {
+                bindings.entry(name).or_insert(rust.clone());
+            }
+            bind_indices(&ty, &rust, &mut bindings);
+            typed.push((ty, rust));
+        }
+    }
+    let result = quote!(__result);
+    bind_indices(&ret, &result, &mut bindings);
+
+    // ... and then collect the clauses that mention them.
+    let mut pre = Vec::new();
+    for (ty, rust) in &typed {
+        refinements(ty, rust, &bindings, &mut pre);
+    }
+    if !requires.is_empty() {
+        clauses(requires, &bindings, &mut pre);
+    }
+    let mut post = Vec::new();
+    refinements(&ret, &result, &bindings, &mut post);
+    if check_index && let Some((ty, index)) = primitive_index(&ret) {
+        let text = quote!(#ty #index);
+        let value = substitute(index.stream().into_iter().collect(), &bindings);
+        post.push(Clause { text, cond: quote!(#result == (#value)) });
+    }
+    if check_index && let Some((ty, valid)) = validity_index(&ret) {
+        let text = quote!(#ty #valid);
+        let value = substitute(valid.stream().into_iter().collect(), &bindings);
+        let is_valid = if ty.to_string() == "Option" { quote!(is_some) } else { quote!(is_ok) };
+        post.push(Clause { text, cond: quote!(#result.#is_valid() == (#value)) });
+    }
+    if let Some(payload) = option_payload(&ret) {
+        let mut some = Vec::new();
+        refinements(&payload, &quote!(*__some), &bindings, &mut some);
+        post.extend(some.into_iter().map(|Clause { text, cond }| Clause {
+            text,
+            cond: quote!(match &__result { Some(__some) => #cond, None => true }),
+        }));
+    }
+    Ok((pre, post))
+}
+
+/// The index `e` of a primitive result type `T[e]`, if it can be computed in Rust.
+fn primitive_index(ty: &[TokenTree]) -> Option<(TokenTree, Group)> {
+    const PRIMITIVES: [&str; 13] = [
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+        "bool",
+    ];
+    match ty {
+        [TokenTree::Ident(id), TokenTree::Group(g)]
+            if g.delimiter() == Delimiter::Bracket
+                && PRIMITIVES.contains(&id.to_string().as_str())
+                && computable(g.stream()) =>
+        {
+            Some((ty[0].clone(), g.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Does the expression mean the same in Rust as in the logic? That is, does it
+/// avoid (refinement) function calls, `if`, `@`, `/` and `%`?
+fn computable(expr: Tokens) -> bool {
+    let toks: Vec<TokenTree> = expr.into_iter().collect();
+    toks.iter().enumerate().all(|(i, t)| match t {
+        TokenTree::Ident(id) => {
+            id != "if"
+                && !matches!(toks.get(i + 1),
+                    Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis)
+        }
+        TokenTree::Punct(p) => !matches!(p.as_char(), '@' | '/' | '%'),
+        TokenTree::Group(g) => g.delimiter() == Delimiter::Parenthesis && computable(g.stream()),
+        TokenTree::Literal(_) => true,
+    })
+}
+
+/// The validity index `p` of an `Option<T>[p]` or `Result<T, E>[p]` result type,
+/// if it can be computed in Rust.
+fn validity_index(ty: &[TokenTree]) -> Option<(TokenTree, Group)> {
+    match ty {
+        [TokenTree::Ident(id), open, .., close, TokenTree::Group(g)]
+            if (id == "Option" || id == "Result")
+                && is_punct(Some(open), '<')
+                && is_punct(Some(close), '>')
+                && g.delimiter() == Delimiter::Bracket
+                && computable(g.stream()) =>
+        {
+            Some((ty[0].clone(), g.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// The payload type `T` of an `Option<T>` (possibly with an index, `Option<T>[b]`).
+fn option_payload(ty: &[TokenTree]) -> Option<Vec<TokenTree>> {
+    match ty {
+        [TokenTree::Ident(id), open, rest @ ..] if id == "Option" && is_punct(Some(open), '<') => {
+            let close = rest.iter().enumerate().rposition(|(i, t)| {
+                is_punct(Some(t), '>') && !is_joint_at(rest, i, '-')
+            })?;
+            Some(rest[..close].to_vec())
+        }
+        _ => None,
+    }
+}
+
+/// Binds each refinement variable `@x` in the indices of `ty` to `value`.
+fn bind_indices(ty: &[TokenTree], value: &Tokens, bindings: &mut HashMap<String, Tokens>) {
+    for g in top_level_groups(ty) {
+        let inner: Vec<TokenTree> = g.stream().into_iter().collect();
+        match g.delimiter() {
+            Delimiter::Bracket => {
+                if let [TokenTree::Punct(at), TokenTree::Ident(x)] = inner.as_slice()
+                    && at.as_char() == '@'
+                {
+                    bindings.entry(x.to_string()).or_insert(value.clone());
+                }
+            }
+            Delimiter::Brace => {
+                if let Some((ty, _)) = constrained(&inner) {
+                    bind_indices(&ty, value, bindings);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Collects the clauses of the refinements `{v: p}`, `{T | p}` and `{p}` of `ty`,
+/// where `value` is what the refined value stands for.
+fn refinements(
+    ty: &[TokenTree],
+    value: &Tokens,
+    bindings: &HashMap<String, Tokens>,
+    out: &mut Vec<Clause>,
+) {
+    for g in top_level_groups(ty) {
+        if g.delimiter() != Delimiter::Brace {
+            continue;
+        }
+        let inner: Vec<TokenTree> = g.stream().into_iter().collect();
+        match inner.as_slice() {
+            [TokenTree::Ident(v), TokenTree::Punct(colon), pred @ ..]
+                if colon.as_char() == ':' && colon.spacing() == Spacing::Alone =>
+            {
+                let mut bindings = bindings.clone();
+                bindings.insert(v.to_string(), value.clone());
+                clauses(pred.to_vec(), &bindings, out);
+            }
+            _ => match constrained(&inner) {
+                Some((_, pred)) => clauses(pred, bindings, out),
+                None => clauses(inner, bindings, out),
+            },
+        }
+    }
+}
+
+/// Splits a constrained type `T | p` into `T` and `p`.
+fn constrained(inner: &[TokenTree]) -> Option<(Vec<TokenTree>, Vec<TokenTree>)> {
+    let bar = inner.iter().enumerate().position(|(i, t)| {
+        is_punct(Some(t), '|')
+            && matches!(t, TokenTree::Punct(p) if p.spacing() == Spacing::Alone)
+            && !is_punct(i.checked_sub(1).and_then(|j| inner.get(j)), '|')
+    })?;
+    Some((inner[..bar].to_vec(), inner[bar + 1..].to_vec()))
+}
+
+/// Splits a predicate into its `&&`-separated clauses (unless it also has a
+/// top-level `||`, which binds more loosely).
+fn clauses(pred: Vec<TokenTree>, bindings: &HashMap<String, Tokens>, out: &mut Vec<Clause>) {
+    let has_or = pred
+        .windows(2)
+        .any(|w| is_joint(&w[0], '|') && is_punct(Some(&w[1]), '|'));
+    let parts = if has_or { vec![pred] } else { split_and(pred) };
+    for part in parts {
+        let text: Tokens = part.iter().cloned().collect();
+        let cond = substitute(part, bindings);
+        out.push(Clause { text, cond });
+    }
+}
+
+fn split_and(pred: Vec<TokenTree>) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut i = 0;
+    while i < pred.len() {
+        if is_joint(&pred[i], '&') && is_punct(pred.get(i + 1), '&') {
+            parts.push(Vec::new());
+            i += 2;
+        } else {
+            parts.last_mut().unwrap().push(pred[i].clone());
+            i += 1;
+        }
+    }
+    parts
+}
+
+/// Replaces each bound name (that is not a field, i.e. follows a `.`) with its value.
+fn substitute(toks: Vec<TokenTree>, bindings: &HashMap<String, Tokens>) -> Tokens {
+    let mut out = Vec::new();
+    let mut after_dot = false;
+    for t in toks {
+        let next_after_dot = is_punct(Some(&t), '.');
+        match &t {
+            TokenTree::Ident(x) if !after_dot && bindings.contains_key(&x.to_string()) => {
+                let value = bindings[&x.to_string()].clone();
+                out.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, value)));
+            }
+            TokenTree::Group(g) => {
+                let mut ng = Group::new(
+                    g.delimiter(),
+                    substitute(g.stream().into_iter().collect(), bindings),
+                );
+                ng.set_span(g.span());
+                out.push(TokenTree::Group(ng));
+            }
+            _ => out.push(t.clone()),
+        }
+        after_dot = next_after_dot;
+    }
+    out.into_iter().collect()
+}
+
+/// The groups of `ty` that are not nested inside generic arguments.
+fn top_level_groups(ty: &[TokenTree]) -> Vec<Group> {
+    let mut depth = 0;
+    let mut groups = Vec::new();
+    for (i, t) in ty.iter().enumerate() {
+        match t {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' && !is_joint_at(ty, i, '-') => depth -= 1,
+            TokenTree::Group(g) if depth == 0 => groups.push(g.clone()),
+            _ => {}
+        }
+    }
+    groups
+}
+
+/// Splits `toks` at the `sep`s that are not nested inside generic arguments.
+fn split_top_level(toks: Vec<TokenTree>, sep: char) -> Vec<Vec<TokenTree>> {
+    let mut depth = 0;
+    let mut parts = vec![Vec::new()];
+    for (i, t) in toks.iter().enumerate() {
+        match t {
+            TokenTree::Punct(p) if p.as_char() == sep && depth == 0 => {
+                parts.push(Vec::new());
+                continue;
+            }
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' && !is_joint_at(&toks, i, '-') => depth -= 1,
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(t.clone());
+    }
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+/// Does the body contain a `return` or `?`, i.e. may it exit early?
+fn exits_early(body: Tokens) -> bool {
+    body.into_iter().any(|t| match t {
+        TokenTree::Ident(id) => id == "return",
+        TokenTree::Punct(p) => p.as_char() == '?',
+        TokenTree::Group(g) => exits_early(g.stream()),
+        TokenTree::Literal(_) => false,
+    })
+}
+
+fn is_punct(t: Option<&TokenTree>, c: char) -> bool {
+    matches!(t, Some(TokenTree::Punct(p)) if p.as_char() == c)
+}
+
+fn is_joint(t: &TokenTree, c: char) -> bool {
+    matches!(t, TokenTree::Punct(p) if p.as_char() == c && p.spacing() == Spacing::Joint)
+}
+
+/// Is the token before position `i` a joint `c`, e.g. the `-` of `->`?
+fn is_joint_at(toks: &[TokenTree], i: usize, c: char) -> bool {
+    i > 0 && is_joint(&toks[i - 1], c)
+}
+
+fn is_keyword(t: &TokenTree, kw: &str) -> bool {
+    matches!(t, TokenTree::Ident(id) if id == kw)
+}
@@ -218,6 +218,7 @@ _require_ that `pop` only be called with _non-empty vectors_.
 
 #[trusted]
 impl<T> RVec<T> {
+    #[contract(n = self.len())]
     #[spec(fn(self: &mut {RVec<T>[@n] | 0 < n}) -> T
            ensures self: RVec<T>[n-1])]
     pub fn pop(&mut self) -> T {
@@ -514,12 +515,16 @@ error[FLUX]: precondition might not hold
 
 As a last example, lets look at a simplified version of the
 #link("https://doc.rust-lang.org/src/core/slice/mod.rs.html#2423-2425")[`binary_search` method from `std::vec`], into which
-we've snuck a tiny little bug
+we've snuck a tiny little bug. Its `spec` is a contract on _both_
+variants of the `Result`: an `Ok` index is within the bounds of the
+vector, and an `Err` index, where `x` would be inserted, is at most
+its length.
 
 */
 
 
 
+#[spec(fn(&RVec<i32>[@n], x: i32) -> Result<usize{v: v < n}, usize{v: v <= n}>)]
 pub fn binary_search(vec: &RVec<i32>, x: i32) -> Result<usize, usize> {
     let mut size = vec.len();
     let mut left = 0;
@@ -574,6 +579,1768 @@ error[FLUX]: arithmetic operation may overflow
 *EXERCISE:* Can you spot the off-by-one and work out a fix?
 ])
 
+== Iterating over Vectors
+
+The `while` loops in `sum_vec` and `fib` make us do all the index
+arithmetic by hand, which is precisely where the off-by-one errors
+creep in. It would be far nicer to just write a `for` loop over
+the elements of an `RVec`. To do so, we need _iterators_ whose
+refinements track how many elements are left to visit.
+
+=== Refined Iterators
+
+Lets define three iterators, one for each way of looking at the
+elements: by shared reference, by mutable reference and by value.
+Each is an opaque wrapper around the corresponding `std` iterator,
+refined by two indices
+
+- `idx`, the number of elements visited so far, and
+- `len`, the total number of elements,
+
+so that an iterator has `len - idx` elements left.
+
+*/
+
+
+
+#[opaque]
+#[refined_by(idx: int, len: int)]
+pub struct Iter<'a, T> {
+    inner: std::slice::Iter<'a, T>,
+}
+
+#[opaque]
+#[refined_by(idx: int, len: int)]
+pub struct IterMut<'a, T> {
+    inner: std::slice::IterMut<'a, T>,
+}
+
+#[opaque]
+#[refined_by(idx: int, len: int)]
+pub struct IntoIter<T> {
+    inner: std::vec::IntoIter<T>,
+}
+
+
+
+/*
+
+=== Creating Iterators
+
+Creating an iterator from an `RVec<T>[n]` gives us one that
+has visited `0` of the `n` elements.
+
+*/
+
+
+
+#[trusted]
+impl<T> RVec<T> {
+    #[spec(fn(&RVec<T>[@n]) -> Iter<T>[0, n])]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { inner: self.inner.iter() }
+    }
+
+    #[spec(fn(&mut RVec<T>[@n]) -> IterMut<T>[0, n])]
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { inner: self.inner.iter_mut() }
+    }
+}
+
+
+
+/*
+
+=== Stepping Iterators
+
+Next, we implement the `Iterator` trait for each of the iterators.
+The standard library's `Iterator` is refined (as described in @ch:08_traits)
+with two associated refinements
+
+- `done`, which says when the iterator has run out of elements, and
+- `step`, which relates the iterator before and after a call to `next`.
+
+For our iterators, an iterator is `done` when it has visited all `len`
+elements, and each `step` bumps up `idx` by one. Accordingly, the
+spec for `next` says that it returns a `Some` (_i.e._ a valid `Option`)
+_exactly when_ there are elements left.
+
+*/
+
+
+
+#[trusted]
+#[assoc(
+    fn done(it: Iter) -> bool { it.idx >= it.len }
+    fn step(it: Iter, nxt: Iter) -> bool { nxt.idx == it.idx + 1 && nxt.len == it.len }
+)]
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[spec(fn(self: &mut Iter<T>[@i, @n]) -> Option<&'a T>[i < n]
+           ensures self: Iter<T>[if i < n { i + 1 } else { i }, n])]
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+}
+
+#[trusted]
+#[assoc(
+    fn done(it: IterMut) -> bool { it.idx >= it.len }
+    fn step(it: IterMut, nxt: IterMut) -> bool { nxt.idx == it.idx + 1 && nxt.len == it.len }
+)]
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    #[spec(fn(self: &mut IterMut<T>[@i, @n]) -> Option<&'a mut T>[i < n]
+           ensures self: IterMut<T>[if i < n { i + 1 } else { i }, n])]
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.inner.next()
+    }
+}
+
+#[trusted]
+#[assoc(
+    fn done(it: IntoIter) -> bool { it.idx >= it.len }
+    fn step(it: IntoIter, nxt: IntoIter) -> bool { nxt.idx == it.idx + 1 && nxt.len == it.len }
+)]
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    #[spec(fn(self: &mut IntoIter<T>[@i, @n]) -> Option<T>[i < n]
+           ensures self: IntoIter<T>[if i < n { i + 1 } else { i }, n])]
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+}
+
+
+
+/*
+
+=== Using `for` Loops
+
+Finally, to let us write `for x in vec` or `for x in &vec`, we implement
+`IntoIterator` for `RVec<T>` and for (mutable) references to `RVec<T>`.
+
+*/
+
+
+
+#[trusted]
+impl<T> IntoIterator for RVec<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    #[spec(fn(RVec<T>[@n]) -> IntoIter<T>[0, n])]
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { inner: self.inner.into_iter() }
+    }
+}
+
+#[trusted]
+impl<'a, T> IntoIterator for &'a RVec<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    #[spec(fn(&RVec<T>[@n]) -> Iter<T>[0, n])]
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+#[trusted]
+impl<'a, T> IntoIterator for &'a mut RVec<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    #[spec(fn(&mut RVec<T>[@n]) -> IterMut<T>[0, n])]
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+
+
+/*
+
+Now we can sum up the elements of a vector without any indices in sight,
+and hence, without any opportunity for an off-by-one error!
+
+*/
+
+
+
+fn sum_vec_iter(vec: &RVec<i32>) -> i32 {
+    let mut res = 0;
+    for x in vec {
+        res += x;
+    }
+    res
+}
+
+
+
+/*
+
+Since the iterator tracks how many elements it has visited,
+Flux can even tell us how many times the body of the loop ran.
+
+*/
+
+
+
+#[spec(fn(&RVec<T>[@n]) -> usize[n])]
+fn count<T>(vec: &RVec<T>) -> usize {
+    let mut res = 0;
+    for _ in vec.iter() {
+        res += 1;
+    }
+    res
+}
+
+
+
+/*
+
+=== Mapping over Vectors
+
+A common use of iteration is to build a _new_ vector by applying
+a function to each element of an old one. The refinements tell us
+that doing so preserves the size: an `RVec<T>[n]` is mapped to an
+`RVec<U>[n]`.
+
+*/
+
+
+
+impl<T> RVec<T> {
+    #[spec(fn(&RVec<T>[@n], f: F) -> RVec<U>[n] where F: FnMut(&T) -> U)]
+    pub fn map<U, F>(&self, mut f: F) -> RVec<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        let mut res = RVec::new();
+        for x in self.iter() {
+            res.push(f(x));
+        }
+        res
+    }
+
+    #[spec(fn(RVec<T>[@n], f: F) -> RVec<U>[n] where F: FnMut(T) -> U)]
+    pub fn into_map<U, F>(self, mut f: F) -> RVec<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        let mut res = RVec::new();
+        for x in self {
+            res.push(f(x));
+        }
+        res
+    }
+}
+
+
+
+/*
+
+For example, we can use `map` to double every element of a vector,
+and Flux knows the result has the same size as the input.
+
+*/
+
+
+
+#[spec(fn(&RVec<i32>[@n]) -> RVec<i32>[n])]
+fn double_all(vec: &RVec<i32>) -> RVec<i32> {
+    vec.map(|x| 2 * x)
+}
+
+
+
+/*
+
+Or, we can use `iter_mut` to update a vector in place.
+
+*/
+
+
+
+fn incr_all(vec: &mut RVec<i32>) {
+    for x in vec.iter_mut() {
+        *x += 1;
+    }
+}
+
+
+
+/*
+
+#alert("info", [
+*NOTE:* The `count`, `map` and `into_map` functions above are _not_
+`#[trusted]`: Flux checks them using only the specifications of `push`
+and the iterators. They do not call `len`, whose specification is
+still the unrefined exercise from earlier in this chapter.
+])
+
+== Editing Vectors
+
+So far, we can only change an `RVec` at its end, via `push` and `pop`.
+Real code also inserts and removes elements in the middle, swaps them
+around, or chops vectors up and glues them back together.
+//
+Lets extend the API with these operations, where, just like with `push`,
+each `spec` says _exactly_ how the size of the vector changes.
+
+=== Inserting and Removing
+
+To `insert` at position `i` we require that `i` is at most the size of the
+vector (inserting at `n` is just a `push`), and to `remove` at position `i`
+we require that `i` is a valid index.
+
+*/
+
+
+
+#[trusted]
+impl<T> RVec<T> {
+    #[contract(n = self.len())]
+    #[spec(fn(self: &mut RVec<T>[@n], i: usize{i <= n}, T)
+           ensures self: RVec<T>[n+1])]
+    pub fn insert(&mut self, i: usize, item: T) {
+        self.inner.insert(i, item);
+    }
+
+    #[contract(n = self.len())]
+    #[spec(fn(self: &mut RVec<T>[@n], i: usize{i < n}) -> T
+           ensures self: RVec<T>[n-1])]
+    pub fn remove(&mut self, i: usize) -> T {
+        self.inner.remove(i)
+    }
+
+    #[spec(fn(self: &mut RVec<T>[@n], i: usize{i < n}, j: usize{j < n})
+           ensures self: RVec<T>[n])]
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.inner.swap(i, j);
+    }
+}
+
+
+
+/*
+
+=== Shrinking
+
+Next, `truncate(k)` keeps (at most) the first `k` elements,
+and `clear` throws _all_ of them away.
+
+*/
+
+
+
+#[trusted]
+impl<T> RVec<T> {
+    #[spec(fn(self: &mut RVec<T>[@n], k: usize)
+           ensures self: RVec<T>[if k < n { k } else { n }])]
+    pub fn truncate(&mut self, k: usize) {
+        self.inner.truncate(k);
+    }
+
+    #[spec(fn(self: &mut RVec<T>[@n]) ensures self: RVec<T>[0])]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+
+
+/*
+
+=== Splitting and Joining
+
+Finally, we can glue the elements of a slice or of another vector
+onto the end of an `RVec`, or `split_off` the tail of a vector at a
+given position. Note that `append` _moves_ all the elements out of
+`other` and hence, strongly updates `other` to be empty.
+
+*/
+
+
+
+#[trusted]
+impl<T> RVec<T> {
+    #[spec(fn(self: &mut RVec<T>[@n], &[T][@m])
+           ensures self: RVec<T>[n+m])]
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.inner.extend_from_slice(other);
+    }
+
+    #[spec(fn(self: &mut RVec<T>[@n], other: &mut RVec<T>[@m])
+           ensures self: RVec<T>[n+m], other: RVec<T>[0])]
+    pub fn append(&mut self, other: &mut RVec<T>) {
+        self.inner.append(&mut other.inner);
+    }
+
+    #[contract(n = self.len())]
+    #[spec(fn(self: &mut RVec<T>[@n], at: usize{at <= n}) -> RVec<T>[n-at]
+           ensures self: RVec<T>[at])]
+    pub fn split_off(&mut self, at: usize) -> RVec<T> {
+        RVec { inner: self.inner.split_off(at) }
+    }
+}
+
+
+
+/*
+
+=== Using the Editing API
+
+Lets take these for a spin: Flux tracks the size through
+each of the edits, and so knows that the result has exactly four elements.
+
+*/
+
+
+
+#[spec(fn () -> RVec<i32>[4])]
+fn test_edits() -> RVec<i32> {
+    let mut v = RVec::new();          // v: RVec<i32>[0]
+    v.extend_from_slice(&[1, 2, 3]);  // v: RVec<i32>[3]
+    v.insert(0, 0);                   // v: RVec<i32>[4]
+    v.swap(0, 3);
+    let mut w = v.split_off(2);       // v: RVec<i32>[2], w: RVec<i32>[2]
+    v.remove(1);                      // v: RVec<i32>[1]
+    v.append(&mut w);                 // v: RVec<i32>[3], w: RVec<i32>[0]
+    v.push(w.len() as i32);           // v: RVec<i32>[4]
+    v
+}
+
+
+
+/*
+
+== Converting Vectors
+
+An `RVec` is not much use if it cannot talk to code that uses
+plain `Vec`s, slices or arrays. Fortunately, Flux already knows
+the sizes of slices and arrays (@ch:06_consts:refined-compile-time-safety),
+and of `Vec`s via extern specs (@ch:07_externs). So we can write
+conversions whose specs carry the size across in both directions.
+
+=== Viewing and Unwrapping
+
+First, we can view an `RVec<T>[n]` as a slice `&[T][n]`, or
+unwrap it into the underlying `Vec<T>[n]`.
+
+*/
+
+
+
+#[trusted]
+impl<T> RVec<T> {
+    #[spec(fn(&RVec<T>[@n]) -> &[T][n])]
+    pub fn as_slice(&self) -> &[T] {
+        self.inner.as_slice()
+    }
+
+    #[spec(fn(&mut RVec<T>[@n]) -> &mut [T][n])]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.inner.as_mut_slice()
+    }
+
+    #[spec(fn(RVec<T>[@n]) -> Vec<T>[n])]
+    pub fn into_inner(self) -> Vec<T> {
+        self.inner
+    }
+}
+
+
+
+/*
+
+=== Building from `Vec`, Slices and Arrays
+
+Going the other way, we implement the standard `From` trait
+so that `RVec::from(...)` or `.into()` work as usual. Note how the
+const-generic size `N` of an array becomes the index of the `RVec`.
+
+*/
+
+
+
+#[trusted]
+impl<T> From<Vec<T>> for RVec<T> {
+    #[spec(fn(Vec<T>[@n]) -> RVec<T>[n])]
+    fn from(inner: Vec<T>) -> RVec<T> {
+        RVec { inner }
+    }
+}
+
+#[trusted]
+impl<T: Clone> From<&[T]> for RVec<T> {
+    #[spec(fn(&[T][@n]) -> RVec<T>[n])]
+    fn from(slice: &[T]) -> RVec<T> {
+        RVec { inner: slice.to_vec() }
+    }
+}
+
+#[trusted]
+impl<T, const N: usize> From<[T; N]> for RVec<T> {
+    #[spec(fn([T; N]) -> RVec<T>[N])]
+    fn from(arr: [T; N]) -> RVec<T> {
+        RVec { inner: Vec::from(arr) }
+    }
+}
+
+#[trusted]
+impl<T> From<RVec<T>> for Vec<T> {
+    #[spec(fn(RVec<T>[@n]) -> Vec<T>[n])]
+    fn from(vec: RVec<T>) -> Vec<T> {
+        vec.inner
+    }
+}
+
+
+
+/*
+
+=== Back to Arrays
+
+Converting an `RVec` back into an array can fail when the sizes
+don't match, so we use `TryFrom`, which hands back the original
+vector if its size is not `N`. The `Err` case says so in its
+refinement: the vector we get back still has size `n`, and `n != N`.
+
+*/
+
+
+
+#[trusted]
+impl<T, const N: usize> TryFrom<RVec<T>> for [T; N] {
+    type Error = RVec<T>;
+
+    #[spec(fn(RVec<T>[@n]) -> Result<[T; N], RVec<T>{m: m == n && n != N}>)]
+    fn try_from(vec: RVec<T>) -> Result<[T; N], RVec<T>> {
+        <[T; N]>::try_from(vec.inner).map_err(|inner| RVec { inner })
+    }
+}
+
+
+
+/*
+
+=== Calling Slice Code
+
+Now `RVec` code can call functions that work on slices, such as the
+`average_color` from @ch:06_consts:refined-compile-time-safety,
+without losing track of sizes: `as_slice` turns an `RVec<Pixel>[n]`
+into a `&[Pixel][n]`, which is exactly what `average_color` needs.
+
+*/
+
+
+
+use crate::ch06_consts::{average_color, Pixel};
+
+#[spec(fn(&RVec<Pixel>[@n], i: usize{i < 3}) -> u64 requires 0 < n)]
+fn average_color_rvec(pixels: &RVec<Pixel>, i: usize) -> u64 {
+    average_color(pixels.as_slice(), i)
+}
+
+fn test_average_color_rvec() {
+    let pixels = RVec::from([[255, 0, 0], [0, 255, 0], [0, 0, 255]]);
+    average_color_rvec(&pixels, 2);       // ok
+    average_color_rvec(&pixels, 3);       // rejected!
+    average_color_rvec(&RVec::new(), 0);  // rejected!
+}
+
+
+
+/*
+
+== Sorted Vectors
+
+The `binary_search` above only promises that the indices it returns are
+in bounds. But the whole point of binary search is to _find_ things! Lets see how
+to write a version whose spec ties the result to the _contents_ of the vector:
+
+- `Ok(i)` means that the element at position `i` is `x`, and
+- `Err(i)` means that `i` is where `x` should be inserted to keep the vector sorted.
+
+=== Tracking the Elements
+
+To talk about the contents, we need an index that tracks them.
+Lets define a `SortedRVec<T>` that, in addition to the `len`, is refined by
+`keys`, a _map_ from each position to the _key_ of the element stored at
+that position.
+//
+Why keys? We want to _compare_ the elements in the refinements, but for
+a generic `T: Ord`, the refinement logic has no notion of how two `T` values
+compare. So we ask that `T` can describe each value by an `int` key, via an
+_associated refinement_ (which we will see in detail in @ch:08_traits),
+together with a comparison method whose `spec` says that it agrees with the keys.
+
+*/
+
+
+
+#[assoc(fn key(me: Self) -> int)]
+pub trait Key: Ord {
+    #[spec(fn(&Self[@a], &Self[@b]) -> bool[Self::key(a) < Self::key(b)])]
+    fn key_lt(&self, other: &Self) -> bool;
+}
+
+#[assoc(fn key(me: int) -> int { me })]
+impl Key for i32 {
+    #[spec(fn(&Self[@a], &Self[@b]) -> bool[Self::key(a) < Self::key(b)])]
+    fn key_lt(&self, other: &Self) -> bool {
+        *self < *other
+    }
+}
+
+#[assoc(fn key(me: int) -> int { me })]
+impl Key for i64 {
+    #[spec(fn(&Self[@a], &Self[@b]) -> bool[Self::key(a) < Self::key(b)])]
+    fn key_lt(&self, other: &Self) -> bool {
+        *self < *other
+    }
+}
+
+#[assoc(fn key(me: int) -> int { me })]
+impl Key for u32 {
+    #[spec(fn(&Self[@a], &Self[@b]) -> bool[Self::key(a) < Self::key(b)])]
+    fn key_lt(&self, other: &Self) -> bool {
+        *self < *other
+    }
+}
+
+
+
+/*
+
+Each integer is its own key, so Flux checks that `key_lt` is just `<`.
+
+*/
+
+
+
+use std::collections::VecDeque;
+
+#[opaque]
+#[refined_by(len: int, keys: Map<int, int>)]
+#[invariant(0 <= len)]
+pub struct SortedRVec<T> {
+    inner: VecDeque<T>,
+}
+
+
+
+/*
+
+It is handy to have a few refinement functions: `at` returns the
+key at position `i`, `is_insert_point` says that `i` is the
+spot where `x` belongs, namely, after all the elements smaller than `x`
+and before all the elements larger than `x`, and `fits_at` is its
+non-strict cousin, which also allows `x` to sit next to equal elements.
+
+*/
+
+
+
+defs! {
+    fn at(v: SortedRVec, i: int) -> int {
+        map_select(v.keys, i)
+    }
+
+    fn is_insert_point(v: SortedRVec, i: int, x: int) -> bool {
+        0 <= i && i <= v.len
+        && (i == 0 || at(v, i - 1) < x)
+        && (i == v.len || x < at(v, i))
+    }
+
+    fn fits_at(v: SortedRVec, i: int, x: int) -> bool {
+        0 <= i && i <= v.len
+        && (i == 0 || at(v, i - 1) <= x)
+        && (i == v.len || x <= at(v, i))
+    }
+}
+
+
+
+/*
+
+=== A Sorted API
+
+The basic API is a thin (trusted) wrapper around a `VecDeque`, so that we
+can take elements from either end in constant time, except that
+`get` returns an element whose key is _exactly_ the one at position `i`,
+and that the only ways to add elements are `insert_at`, whose precondition
+says the element must fit at that position, and `append`, whose precondition
+says that the elements of `other` can all go after those of `self`.
+We can also `pop` the last element, which leaves the other keys as they were,
+or `pop_front` the first one, after which the new first key is no smaller.
+//
+Since `new` starts out empty, and `insert_at` and `append` only ever put
+elements where they fit, the keys are always in order. The method `sorted` makes
+this invariant available to Flux: it tells us that the key at position `i`
+is at most the key at any later position `j`.
+
+*/
+
+
+
+#[trusted]
+impl<T: Key> SortedRVec<T> {
+    #[spec(fn() -> SortedRVec<T>{v: v.len == 0})]
+    pub fn new() -> Self {
+        SortedRVec { inner: VecDeque::new() }
+    }
+
+    #[spec(fn(&SortedRVec<T>[@v]) -> usize[v.len])]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[spec(fn(&SortedRVec<T>[@v]) -> bool[v.len == 0])]
+    pub fn is_empty(&self) -> bool {
+        self.inner.len() == 0
+    }
+
+    #[spec(fn(&SortedRVec<T>[@v], i: usize{i < v.len}) -> &T{e: T::key(e) == at(v, i)})]
+    pub fn get(&self, i: usize) -> &T {
+        &self.inner[i]
+    }
+
+    #[spec(fn(self: &mut SortedRVec<T>[@v], i: usize, x: T{fits_at(v, i, T::key(x))})
+           ensures self: SortedRVec<T>{w: w.len == v.len + 1 && at(w, i) == T::key(x)})]
+    fn insert_at(&mut self, i: usize, x: T) {
+        self.inner.insert(i, x);
+    }
+
+    #[spec(fn(self: &mut SortedRVec<T>[@v]) -> T{e: T::key(e) == at(v, v.len - 1)}
+           requires 0 < v.len
+           ensures self: SortedRVec<T>{w: w.len == v.len - 1 && w.keys == v.keys})]
+    pub fn pop(&mut self) -> T {
+        self.inner.pop_back().unwrap()
+    }
+
+    #[spec(fn(self: &mut SortedRVec<T>[@v]) -> T{e: T::key(e) == at(v, 0)}
+           requires 0 < v.len
+           ensures self: SortedRVec<T>{w: w.len == v.len - 1 && (w.len == 0 || at(v, 0) <= at(w, 0))})]
+    pub fn pop_front(&mut self) -> T {
+        self.inner.pop_front().unwrap()
+    }
+
+    #[spec(fn(self: &mut SortedRVec<T>[@v], other: SortedRVec<T>[@o])
+           requires v.len == 0 || o.len == 0 || at(v, v.len - 1) <= at(o, 0)
+           ensures self: SortedRVec<T>{w: w.len == v.len + o.len})]
+    pub fn append(&mut self, mut other: SortedRVec<T>) {
+        self.inner.append(&mut other.inner);
+    }
+
+    #[spec(fn(&SortedRVec<T>[@v], i: usize, j: usize)
+           requires i <= j && j < v.len
+           ensures at(v, i) <= at(v, j))]
+    pub fn sorted(&self, i: usize, j: usize) {
+        debug_assert!(self.inner[i] <= self.inner[j]);
+    }
+}
+
+impl<T: Key> Default for SortedRVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+
+/*
+
+=== Verified Binary Search
+
+Now for the search itself. Rather than use a `while` loop, whose invariant Flux
+would have to _infer_, we write a recursive `search` whose precondition _is_
+the invariant: every element to the left of `lo` is smaller than `x` and every
+element at or to the right of `hi` is larger than `x`.
+
+*/
+
+
+
+impl<T: Key> SortedRVec<T> {
+    #[spec(fn(&SortedRVec<T>[@v], x: &T[@a], lo: usize, hi: usize)
+              -> Result<usize{i: i < v.len && at(v, i) == T::key(a)},
+                        usize{i: is_insert_point(v, i, T::key(a))}>
+           requires lo <= hi && hi <= v.len
+                 && (lo == 0 || at(v, lo - 1) < T::key(a))
+                 && (hi == v.len || T::key(a) < at(v, hi)))]
+    fn search(&self, x: &T, lo: usize, hi: usize) -> Result<usize, usize> {
+        if lo == hi {
+            return Err(lo);
+        }
+        let mid = lo + (hi - lo) / 2;
+        let val = self.get(mid);
+        if val.key_lt(x) {
+            self.search(x, mid + 1, hi)
+        } else if x.key_lt(val) {
+            self.search(x, lo, mid)
+        } else {
+            Ok(mid)
+        }
+    }
+
+    #[spec(fn(&SortedRVec<T>[@v], x: &T[@a])
+              -> Result<usize{i: i < v.len && at(v, i) == T::key(a)},
+                        usize{i: is_insert_point(v, i, T::key(a))}>)]
+    pub fn binary_search(&self, x: &T) -> Result<usize, usize> {
+        self.search(x, 0, self.len())
+    }
+}
+
+
+
+/*
+
+#alert("info", [
+*Where is Sortedness?* Curiously, `search` never calls `sorted`: the
+_insertion point_ property holds for _any_ vector! Sortedness is what
+makes the result _useful_: in a sorted vector, an `Err` means that `x`
+does not occur anywhere at all.
+])
+
+=== Keeping Things Sorted
+
+To add an element, `insert` uses `binary_search` to find where the new
+element belongs. An `Err(i)` is an insertion point, so `x` fits at `i`.
+An `Ok(i)` only tells us that the key at `i` equals that of `x`, so to
+show that `x` fits at `i`, we call `sorted` to learn that the key just
+before `i` is no larger. Flux checks `insert` _without_ any `#[trusted]`:
+if we drop the call to `sorted`, or insert at some other position,
+the precondition of `insert_at` fails.
+
+*/
+
+
+
+impl<T: Key> SortedRVec<T> {
+    #[spec(fn(self: &mut SortedRVec<T>[@v], x: T)
+           ensures self: SortedRVec<T>{w: w.len == v.len + 1})]
+    pub fn insert(&mut self, x: T) {
+        let i = match self.binary_search(&x) {
+            Ok(i) => {
+                if 0 < i {
+                    self.sorted(i - 1, i);
+                }
+                i
+            }
+            Err(i) => i,
+        };
+        self.insert_at(i, x);
+    }
+}
+
+
+
+/*
+
+Clients can now use the result of `binary_search` to look up
+values without any runtime checks.
+
+*/
+
+
+
+fn test_sorted(table: &SortedRVec<i32>, key: i32) -> bool {
+    match table.binary_search(&key) {
+        Ok(i) => {
+            assert(*table.get(i) == key);
+            true
+        }
+        Err(i) => {
+            assert(i == 0 || *table.get(i - 1) < key);
+            assert(i == table.len() || key < *table.get(i));
+            false
+        }
+    }
+}
+
+
+
+/*
+
+== Ring Buffers
+
+An `RVec` can grow without bound, but sometimes we want a _bounded_ queue,
+for example to keep only the last few readings from a sensor. Lets use `RVec`
+to build a fixed-capacity, first-in-first-out _ring buffer_.
+
+=== A Refined Ring Buffer
+
+The `RingBuf` is refined by its `len` (the number of elements currently
+stored) and its `cap` (the maximum number of elements it can hold).
+Unlike `RVec`, the `RingBuf` is _not_ opaque: its fields are refined in terms
+of the indices, which lets Flux check the implementation itself.
+
+*/
+
+
+
+#[refined_by(len: int, cap: int)]
+#[invariant(0 <= len && len <= cap && 0 < cap)]
+pub struct RingBuf<T> {
+    #[field(RVec<T>[cap])]
+    buf: RVec<T>,
+    #[field(usize[cap])]
+    cap: usize,
+    #[field(usize{v: v < cap})]
+    head: usize,
+    #[field(usize[len])]
+    len: usize,
+}
+
+
+
+/*
+
+The fields say that
+
+- `buf` is an `RVec` with exactly `cap` slots,
+- `cap` holds the capacity at run-time,
+- `head` is the (valid) position of the oldest element, and
+- `len` holds the number of elements at run-time.
+
+The elements of the queue live in the slots `head`, `head + 1`, ...
+wrapping around to `0` once we fall off the end of `buf`.
+
+=== Creating a Ring Buffer
+
+We create a ring buffer by filling all `cap` slots with default values.
+
+*/
+
+
+
+impl<T: Default> RingBuf<T> {
+    #[spec(fn(cap: usize{0 < cap}) -> RingBuf<T>[0, cap])]
+    pub fn with_capacity(cap: usize) -> Self {
+        let mut buf = RVec::new();
+        let mut i = 0;
+        while i < cap {
+            buf.push(T::default());
+            i += 1;
+        }
+        RingBuf { buf, cap, head: 0, len: 0 }
+    }
+}
+
+
+
+/*
+
+=== Wrapping Around
+
+The crux of the implementation is `slot`, which computes the position in
+`buf` of the `i`-th element of the queue. As `head` and `i` are both
+smaller than `cap`, subtracting `cap` once suffices to wrap around,
+and Flux checks that the resulting position is always in bounds.
+
+*/
+
+
+
+impl<T> RingBuf<T> {
+    #[spec(fn(&RingBuf<T>[@len, @cap], i: usize{i < cap}) -> usize{v: v < cap})]
+    fn slot(&self, i: usize) -> usize {
+        let j = self.head + i;
+        if j < self.cap { j } else { j - self.cap }
+    }
+}
+
+
+
+/*
+
+=== Querying the Ring Buffer
+
+The queries simply return the run-time fields, which Flux knows
+are equal to the indices. We can only `get` the `i`-th oldest
+element when `i` is less than `len`.
+
+*/
+
+
+
+impl<T> RingBuf<T> {
+    #[spec(fn(&RingBuf<T>[@len, @cap]) -> usize[len])]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[spec(fn(&RingBuf<T>[@len, @cap]) -> usize[cap])]
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    #[spec(fn(&RingBuf<T>[@len, @cap]) -> bool[len == 0])]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[spec(fn(&RingBuf<T>[@len, @cap]) -> bool[len == cap])]
+    pub fn is_full(&self) -> bool {
+        self.len == self.cap
+    }
+
+    #[spec(fn(&RingBuf<T>[@len, @cap], i: usize{i < len}) -> &T)]
+    pub fn get(&self, i: usize) -> &T {
+        self.buf.get(self.slot(i))
+    }
+}
+
+
+
+/*
+
+=== Pushing and Popping
+
+We can `push_back` only when the buffer is not full, and `pop_front`
+only when it is not empty; the `ensures` clauses track the new `len`.
+Only `pop_front` needs `T: Default`, as it uses `take` to move the
+oldest element out of its slot, leaving a default value behind.
+
+*/
+
+
+
+impl<T> RingBuf<T> {
+    #[spec(fn(self: &mut RingBuf<T>[@len, @cap], T)
+           requires len < cap
+           ensures self: RingBuf<T>[len + 1, cap])]
+    pub fn push_back(&mut self, item: T) {
+        let i = self.slot(self.len);
+        self.buf[i] = item;
+        self.len += 1;
+    }
+}
+
+impl<T: Default> RingBuf<T> {
+    #[spec(fn(self: &mut RingBuf<T>[@len, @cap]) -> T
+           requires 0 < len
+           ensures self: RingBuf<T>[len - 1, cap])]
+    pub fn pop_front(&mut self) -> T {
+        let item = std::mem::take(&mut self.buf[self.head]);
+        self.head = if self.head + 1 < self.cap { self.head + 1 } else { 0 };
+        self.len -= 1;
+        item
+    }
+}
+
+
+
+/*
+
+=== Using the Ring Buffer
+
+Here is a function that keeps the most recent `readings` in a ring buffer,
+dropping the oldest one whenever the buffer fills up. As `pop_front` makes
+room when the buffer `is_full`, Flux accepts the call to `push_back`.
+
+*/
+
+
+
+#[spec(fn(buf: &mut RingBuf<i32>[@len, @cap], reading: i32)
+       ensures buf: RingBuf<i32>{v: 0 < v.len && v.cap == cap})]
+fn record(buf: &mut RingBuf<i32>, reading: i32) {
+    if buf.is_full() {
+        buf.pop_front();
+    }
+    buf.push_back(reading);
+}
+
+fn test_ring_buf() {
+    let mut buf = RingBuf::with_capacity(2); // buf: RingBuf<i32>[0, 2]
+    buf.push_back(1);                        // buf: RingBuf<i32>[1, 2]
+    buf.push_back(2);                        // buf: RingBuf<i32>[2, 2]
+    let oldest = buf.get(0);                 // ok, as 0 < 2
+    buf.pop_front();                         // buf: RingBuf<i32>[1, 2]
+    buf.push_back(3);                        // buf: RingBuf<i32>[2, 2]
+    buf.push_back(4);                        // rejected!
+}
+
+
+
+/*
+
+== Matrices
+
+Way back at the start of this chapter we said that `RVec<RVec<f32>[n]>[m]`
+represents a vector of `m` vectors, each of size `n`, i.e. an `m` by `n`
+_matrix_. Lets make that an actual type, so that Flux can catch dimension
+mismatches, for example, when multiplying matrices.
+
+=== Initializing Vectors
+
+To build matrices, it is convenient to have a helper that uses a closure
+to compute each element of a vector of a given size. The spec says that
+the closure is only ever called with _valid_ positions `i < n`, which lets
+the closure itself safely index into other vectors of size `n`.
+
+*/
+
+
+
+#[spec(fn(n: usize, f: F) -> RVec<A>[n] where F: FnMut(usize{v: v < n}) -> A)]
+fn init<F, A>(n: usize, mut f: F) -> RVec<A>
+where
+    F: FnMut(usize) -> A,
+{
+    let mut res = RVec::new();
+    let mut i = 0;
+    while i < n {
+        res.push(f(i));
+        i += 1;
+    }
+    res
+}
+
+
+
+/*
+
+=== A Refined Matrix
+
+A `Matrix` is refined by its number of `rows` and `cols`. The `data` field is
+a vector of `rows` many rows, each of which is a vector of size `cols`, and the
+`rows` and `cols` fields hold the dimensions at run-time.
+
+*/
+
+
+
+#[refined_by(rows: int, cols: int)]
+pub struct Matrix<T> {
+    #[field(usize[rows])]
+    rows: usize,
+    #[field(usize[cols])]
+    cols: usize,
+    #[field(RVec<RVec<T>[cols]>[rows])]
+    data: RVec<RVec<T>>,
+}
+
+
+
+/*
+
+=== Creating Matrices
+
+We can create a matrix from a vector of rows (which must all have the same size),
+or a matrix of all zeros, or the square identity matrix.
+
+*/
+
+
+
+impl<T> Matrix<T> {
+    #[spec(fn(cols: usize, data: RVec<RVec<T>[cols]>[@rows]) -> Matrix<T>[rows, cols])]
+    pub fn from_rows(cols: usize, data: RVec<RVec<T>>) -> Self {
+        let rows = data.len();
+        Matrix { rows, cols, data }
+    }
+
+    #[spec(fn(rows: usize, cols: usize) -> Matrix<T>[rows, cols])]
+    pub fn zeros(rows: usize, cols: usize) -> Self
+    where
+        T: Default,
+    {
+        let data = init(rows, |_| init(cols, |_| T::default()));
+        Matrix { rows, cols, data }
+    }
+
+    #[spec(fn(n: usize) -> Matrix<T>[n, n])]
+    pub fn identity(n: usize) -> Self
+    where
+        T: Default + From<u8>,
+    {
+        let data = init(n, |i| init(n, |j| if i == j { T::from(1) } else { T::default() }));
+        Matrix { rows: n, cols: n, data }
+    }
+}
+
+
+
+/*
+
+#alert("success", [
+*EXERCISE:* `from_rows` uses `data.len()` to compute the number of `rows`.
+Which `spec` from earlier in this chapter do you need to fix, so that
+Flux accepts `from_rows`?
+])
+
+=== Accessing Elements
+
+The dimensions are available at run-time via `rows` and `cols`,
+and we can `get` an element only at a valid row _and_ column.
+
+*/
+
+
+
+impl<T> Matrix<T> {
+    #[spec(fn(&Matrix<T>[@m, @n]) -> usize[m])]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[spec(fn(&Matrix<T>[@m, @n]) -> usize[n])]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    #[spec(fn(&Matrix<T>[@m, @n], r: usize{r < m}, c: usize{c < n}) -> &T)]
+    pub fn get(&self, r: usize, c: usize) -> &T {
+        self.data.get(r).get(c)
+    }
+}
+
+
+
+/*
+
+=== Transpose and Multiply
+
+The `transpose` of an `m` by `n` matrix is an `n` by `m` matrix,
+and we can only multiply an `m` by `k` matrix with a `k` by `n`
+matrix, getting back an `m` by `n` matrix.
+
+*/
+
+
+
+impl<T: Copy> Matrix<T> {
+    #[spec(fn(&Matrix<T>[@m, @n]) -> Matrix<T>[n, m])]
+    pub fn transpose(&self) -> Matrix<T> {
+        let data = init(self.cols, |c| init(self.rows, |r| *self.get(r, c)));
+        Matrix { rows: self.cols, cols: self.rows, data }
+    }
+
+    #[spec(fn(&Matrix<T>[@m, @k], &Matrix<T>[k, @n]) -> Matrix<T>[m, n])]
+    pub fn mul(&self, other: &Matrix<T>) -> Matrix<T>
+    where
+        T: Default + std::ops::Add<Output = T> + std::ops::Mul<Output = T>,
+    {
+        let data = init(self.rows, |i| {
+            init(other.cols, |j| {
+                let mut sum = T::default();
+                let mut l = 0;
+                while l < self.cols {
+                    sum = sum + *self.get(i, l) * *other.get(l, j);
+                    l += 1;
+                }
+                sum
+            })
+        });
+        Matrix { rows: self.rows, cols: other.cols, data }
+    }
+}
+
+
+
+/*
+
+=== Catching Dimension Mismatches
+
+Now Flux will catch dimension mismatches at compile time.
+
+*/
+
+
+
+fn test_matrix() {
+    let a: Matrix<f32> = Matrix::zeros(2, 3); // a: Matrix<f32>[2, 3]
+    let b = Matrix::identity(3);              // b: Matrix<f32>[3, 3]
+    let c = a.mul(&b);                        // c: Matrix<f32>[2, 3]
+    let d = c.transpose();                    // d: Matrix<f32>[3, 2]
+    let e = d.mul(&a);                        // e: Matrix<f32>[3, 3]
+    let x = e.get(2, 2);                      // ok
+    let f = a.mul(&d.transpose());            // rejected!
+}
+
+
+
+/*
+
+== Memoization, Revisited
+
+The `fib` function we saw earlier mixes up two concerns: the arithmetic
+that computes fibonacci numbers, and the bookkeeping that makes sure we
+only look up entries that have already been computed. Lets pull out the
+bookkeeping into a reusable _memo table_ that we can use for all manner
+of dynamic programming algorithms.
+
+=== A Memo Table
+
+A `Memo` is refined by its `len`, with the understanding that the
+entries `0`, ..., `len - 1` have been _filled_, and all others have not.
+Entries are filled in order, one at a time, and the `table` field
+is an `RVec` holding exactly the filled entries.
+
+*/
+
+
+
+#[refined_by(len: int)]
+pub struct Memo<T> {
+    #[field(RVec<T>[len])]
+    table: RVec<T>,
+    #[field(usize[len])]
+    len: usize,
+}
+
+
+
+/*
+
+The API says that we can only `get` entry `i` once the table has
+more than `i` entries, i.e. once entry `i` has been filled.
+
+*/
+
+
+
+impl<T> Memo<T> {
+    #[spec(fn() -> Memo<T>[0])]
+    pub fn new() -> Self {
+        Memo { table: RVec::new(), len: 0 }
+    }
+
+    #[spec(fn(&Memo<T>[@n]) -> usize[n])]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[spec(fn(&Memo<T>[@n]) -> bool[n == 0])]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[spec(fn(&Memo<T>[@n], i: usize{i < n}) -> &T)]
+    pub fn get(&self, i: usize) -> &T {
+        self.table.get(i)
+    }
+
+    #[spec(fn(self: &mut Memo<T>[@n], T) ensures self: Memo<T>[n+1])]
+    pub fn fill(&mut self, val: T) {
+        self.table.push(val);
+        self.len += 1;
+    }
+
+    #[spec(fn(Memo<T>[@n]) -> RVec<T>[n])]
+    pub fn into_rvec(self) -> RVec<T> {
+        self.table
+    }
+}
+
+impl<T> Default for Memo<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+
+/*
+
+=== Fibonacci
+
+Here is the `fib` function from the start of this chapter, ported to `Memo`:
+the loop fills entry `i` by looking up the two previously filled entries.
+As the loop runs up to _and including_ `n`, the table always has `n + 1`
+entries at the end, so, unlike the `pop` in `fib`, the final `get` cannot fail.
+//
+The precondition `n <= 93` keeps the result within a `u64`,
+as the 94th fibonacci number is too large. Note that Flux does _not_
+check this: the entries of the `Memo` are plain `u64`s, so Flux knows
+nothing about their size, and hence, cannot prove that `a + b` does not
+overflow. That the 93rd fibonacci number fits is a run-time fact, which
+we check with a test.
+
+*/
+
+
+
+#[spec(fn(n: usize{n <= 93}) -> u64)]
+pub fn fib_memo(n: usize) -> u64 {
+    let mut memo = Memo::new();         // memo: Memo<u64>[0]
+    let mut i = 0;
+    while i <= n {
+        if i == 0 {
+            memo.fill(0);
+        } else if i == 1 {
+            memo.fill(1);
+        } else {
+            let a = *memo.get(i - 1);
+            let b = *memo.get(i - 2);
+            memo.fill(a + b);
+        }
+        i += 1;                         // memo: Memo<u64>[i]
+    }
+    *memo.get(n)
+}
+
+
+
+/*
+
+=== Two Dimensional Tables
+
+Many dynamic programming algorithms fill in a _two_ dimensional
+table. We can represent these as a `Memo` of rows, where each row
+is itself a `Memo` of size `n + 1`. Each entry is computed from
+entries in the _previous_ row (which is in the outer table) and
+from the _previous_ entries of the current row.
+
+==== Longest Common Subsequence
+
+For example, here is the length of the longest common subsequence of `a` and
+`b`, where entry `j` of row `i` is the answer for the prefixes `a[..i]` and
+`b[..j]`.
+
+*/
+
+
+
+#[spec(fn(a: &[u8][@m], b: &[u8][@n]) -> usize)]
+pub fn lcs(a: &[u8], b: &[u8]) -> usize {
+    let m = a.len();
+    let n = b.len();
+    let mut table: Memo<Memo<usize>> = Memo::new();
+    let mut i = 0;
+    while i <= m {
+        let mut row = Memo::new();
+        let mut j = 0;
+        while j <= n {
+            let val = if i == 0 || j == 0 {
+                0
+            } else if a[i - 1] == b[j - 1] {
+                table.get(i - 1).get(j - 1) + 1
+            } else {
+                let up = *table.get(i - 1).get(j);
+                let left = *row.get(j - 1);
+                up.max(left)
+            };
+            row.fill(val);
+            j += 1;
+        }
+        table.fill(row);
+        i += 1;
+    }
+    *table.get(m).get(n)
+}
+
+
+
+/*
+
+==== 0/1 Knapsack
+
+Next, the classic knapsack problem: given items with `weights` and `values`,
+what is the most value that fits in a knapsack of capacity `cap`?
+Entry `w` of row `i` is the best value using the first `i` items with
+capacity `w`. Note that Flux checks that `w - weight` is a valid column.
+As the total value of the items may not fit in a `u64`, we add values with
+`saturating_add` (@ch:externs), so the result is capped at `u64::MAX`
+rather than overflowing.
+
+*/
+
+
+
+#[spec(fn(weights: &[usize][@k], values: &[u64][k], cap: usize) -> u64)]
+pub fn knapsack(weights: &[usize], values: &[u64], cap: usize) -> u64 {
+    let k = weights.len();
+    let mut table: Memo<Memo<u64>> = Memo::new();
+    let mut i = 0;
+    while i <= k {
+        let mut row = Memo::new();
+        let mut w = 0;
+        while w <= cap {
+            let val = if i == 0 {
+                0
+            } else {
+                let skip = *table.get(i - 1).get(w);
+                let weight = weights[i - 1];
+                if weight <= w {
+                    let take = table.get(i - 1).get(w - weight).saturating_add(values[i - 1]);
+                    skip.max(take)
+                } else {
+                    skip
+                }
+            };
+            row.fill(val);
+            w += 1;
+        }
+        table.fill(row);
+        i += 1;
+    }
+    *table.get(k).get(cap)
+}
+
+
+
+/*
+
+==== Edit Distance
+
+Finally, the edit (Levenshtein) distance between `a` and `b`, where
+entry `j` of row `i` is the distance between `a[..i]` and `b[..j]`.
+
+*/
+
+
+
+#[spec(fn(a: &[u8][@m], b: &[u8][@n]) -> usize)]
+pub fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let m = a.len();
+    let n = b.len();
+    let mut table: Memo<Memo<usize>> = Memo::new();
+    let mut i = 0;
+    while i <= m {
+        let mut row = Memo::new();
+        let mut j = 0;
+        while j <= n {
+            let val = if i == 0 {
+                j
+            } else if j == 0 {
+                i
+            } else {
+                let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+                let replace = table.get(i - 1).get(j - 1) + cost;
+                let delete = table.get(i - 1).get(j) + 1;
+                let insert = row.get(j - 1) + 1;
+                replace.min(delete).min(insert)
+            };
+            row.fill(val);
+            j += 1;
+        }
+        table.fill(row);
+        i += 1;
+    }
+    *table.get(m).get(n)
+}
+
+
+
+/*
+
+In each case, Flux infers that the outer `table` has `i` entries,
+each of size `n + 1`, and that the current `row` has `j` entries,
+and hence, that every lookup is of an entry that has already been filled.
+
+== Sorting
+
+Sorting is a rite of passage for verification tools: the algorithms are
+short, but are chock-full of index arithmetic that is easy to get wrong,
+and their whole point is a property, _sortedness_, that is easy to state
+but hard to check. Lets implement four classic sorting algorithms, and
+have Flux check that each of them _preserves the size_ of the vector,
+_never_ indexes out of bounds, and, for those that return a `SortedRVec`,
+that the result is indeed sorted.
+
+=== Insertion Sort
+
+Insertion sort grows a sorted vector by `insert`ing each element
+where it belongs. As `insert` is verified, there is nothing more to do!
+
+*/
+
+
+
+#[spec(fn(vec: RVec<T>[@n]) -> SortedRVec<T>{v: v.len == n})]
+pub fn insertion_sort<T: Key>(vec: RVec<T>) -> SortedRVec<T> {
+    let mut res = SortedRVec::new();
+    for x in vec {
+        res.insert(x);
+    }
+    res
+}
+
+
+
+/*
+
+=== Selection Sort
+
+Selection sort repeatedly finds the smallest element of the suffix
+`vec[i..]` and `swap`s it into position `i`. It works _in-place_ on
+an `RVec`, which does not track its elements, so here Flux only checks
+that `selection_sort` preserves the size and stays within bounds. As the
+`spec` of `len` is still the exercise from above, we get the size of the
+vector from `as_slice`, whose `spec` says that the slice has `n` elements.
+
+*/
+
+
+
+#[spec(fn(vec: &mut RVec<T>[@n]) ensures vec: RVec<T>[n])]
+pub fn selection_sort<T: Ord>(vec: &mut RVec<T>) {
+    let n = vec.as_slice().len();
+    let mut i = 0;
+    while i < n {
+        let mut min = i;
+        let mut j = i + 1;
+        while j < n {
+            if vec[j] < vec[min] {
+                min = j;
+            }
+            j += 1;
+        }
+        vec.swap(i, min);
+        i += 1;
+    }
+}
+
+
+
+/*
+
+=== Quicksort
+
+Quicksort picks a `pivot`, splits the other elements into those that
+are `less` than the pivot and those that are `more`, sorts each part
+recursively, and glues the results back together around the pivot.
+//
+The trick is in the _types_ of the two parts: Flux infers that `less` is an
+`RVec<T{e: T::key(e) < T::key(pivot)}>`, that is, a vector whose elements
+are all smaller than the `pivot`. Sorting `less` keeps the element type, so
+when we `get` the last (i.e. largest) element of the sorted `less`, Flux learns
+that it is smaller than the pivot, which is what we need to put the
+pivot after it. Similarly, getting the first element of `more` tells Flux
+that everything in `more` can go after the pivot.
+
+*/
+
+
+
+#[spec(fn(vec: RVec<T>[@n]) -> SortedRVec<T>{v: v.len == n})]
+pub fn quicksort<T: Key>(mut vec: RVec<T>) -> SortedRVec<T> {
+    let n = vec.as_slice().len();
+    if n == 0 {
+        return SortedRVec::new();
+    }
+    let pivot = vec.pop();
+    let mut less = RVec::new();
+    let mut more = RVec::new();
+    for x in vec {
+        if x.key_lt(&pivot) {
+            less.push(x);
+        } else {
+            more.push(x);
+        }
+    }
+    let less = quicksort(less);
+    let more = quicksort(more);
+    if !less.is_empty() {
+        less.get(less.len() - 1); // the largest of `less` is below `pivot`
+    }
+    if !more.is_empty() {
+        more.get(0);              // the smallest of `more` is above `pivot`
+    }
+    let mut res = less;
+    res.insert_at(res.len(), pivot);
+    res.append(more);
+    res
+}
+
+
+
+/*
+
+=== Merge Sort
+
+Merge sort splits the vector in half, sorts each half recursively, and
+then `merge`s the two sorted halves, front to back: while both halves
+are non-empty, we `pop_front` the smaller of their first elements, and
+put it at the _end_ of the result `res`. Once one of the halves runs
+out, we `append` the other to `res`. The loop invariant says that the
+last element of `res` is no larger than the first elements of `a` and
+`b`, and that `res`, `a` and `b` together hold all the elements. We
+help Flux infer it with a couple of _qualifiers_.
+
+*/
+
+
+
+defs! {
+    qualifier MergedBefore(r: SortedRVec, v: SortedRVec) {
+        r.len == 0 || v.len == 0 || at(r, r.len - 1) <= at(v, 0)
+    }
+
+    qualifier MergedLen(r: SortedRVec, a: SortedRVec, b: SortedRVec, va: SortedRVec, vb: SortedRVec) {
+        r.len + a.len + b.len == va.len + vb.len
+    }
+}
+
+#[spec(fn(a: SortedRVec<T>[@va], b: SortedRVec<T>[@vb]) -> SortedRVec<T>{v: v.len == va.len + vb.len})]
+fn merge<T: Key>(mut a: SortedRVec<T>, mut b: SortedRVec<T>) -> SortedRVec<T> {
+    let mut res = SortedRVec::new();
+    while !a.is_empty() && !b.is_empty() {
+        let x = if b.get(0).key_lt(a.get(0)) { b.pop_front() } else { a.pop_front() };
+        res.insert_at(res.len(), x);
+    }
+    if a.is_empty() {
+        res.append(b);
+    } else {
+        res.append(a);
+    }
+    res
+}
+
+#[spec(fn(vec: RVec<T>[@n]) -> SortedRVec<T>{v: v.len == n})]
+pub fn merge_sort<T: Key>(mut vec: RVec<T>) -> SortedRVec<T> {
+    let n = vec.as_slice().len();
+    if n <= 1 {
+        return insertion_sort(vec);
+    }
+    let right = vec.split_off(n / 2);
+    merge(merge_sort(vec), merge_sort(right))
+}
+
+
+
+/*
+
+#alert("success", [
+*EXERCISE:* Can you write a version of `selection_sort` that returns a
+`SortedRVec`? _Hint:_ Rather than swapping the smallest element into
+place, `remove` the _largest_ element from the `RVec` and put it at the
+front of the result. What does Flux need to know about the elements
+that are left behind?
+])
+
+=== From Sorting to Searching
+
+As the sorts return a `SortedRVec`, we can directly use the verified
+`binary_search` on their result.
+
+*/
+
+
+
+fn test_sort_search() {
+    let sorted = quicksort(RVec::from([30, 10, 20]));
+    if let Ok(i) = sorted.binary_search(&20) {
+        assert(*sorted.get(i) == 20);
+    }
+}
+
+
+
+/*
+
 == Summary
 
 We just saw how Flux's index and constraint
@@ -587,3 +2354,240 @@ simple ones, as we will see when we use `RVec` to implement
 sparse matrices in @ch:11_sparse, and
 a small neural network library in @ch:12_neural.
 */
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::props::*;
+    use rand::Rng;
+    use std::collections::VecDeque;
+
+    #[allow(clippy::ptr_arg)] // `for_all` wants a `Fn(&Vec<i64>)`
+    fn shrink_ints(xs: &Vec<i64>) -> Vec<Vec<i64>> {
+        shrink_vec(xs, shrink_i64)
+    }
+
+    fn shrink_at(p: &(Vec<i64>, usize)) -> Vec<(Vec<i64>, usize)> {
+        shrink_pair(p, shrink_ints, shrink_usize)
+    }
+
+    #[test]
+    fn prop_push() {
+        for_all("push", |rng| gen_vec(rng, 0, 20), shrink_ints, |_| true, |xs| {
+            let mut v = RVec::from(xs.as_slice());
+            v.push(7);
+            v.len() == xs.len() + 1 && v[xs.len()] == 7
+        });
+    }
+
+    #[test]
+    fn prop_pop() {
+        for_all(
+            "pop",
+            |rng| gen_vec(rng, 1, 20),
+            shrink_ints,
+            |xs| RVec::from(xs.as_slice()).pop_requires(),
+            |xs| {
+                let mut v = RVec::from(xs.as_slice());
+                let x = v.pop();
+                v.len() == xs.len() - 1 && Some(&x) == xs.last()
+            },
+        );
+    }
+
+    #[test]
+    fn prop_insert() {
+        for_all(
+            "insert",
+            |rng| {
+                let xs = gen_vec(rng, 0, 20);
+                let i = rng.gen_range(0..=xs.len());
+                (xs, i)
+            },
+            shrink_at,
+            |(xs, i)| RVec::from(xs.as_slice()).insert_requires(*i, 7),
+            |(xs, i)| {
+                let mut v = RVec::from(xs.as_slice());
+                v.insert(*i, 7);
+                v.len() == xs.len() + 1 && v[*i] == 7
+            },
+        );
+    }
+
+    #[test]
+    fn prop_remove() {
+        for_all(
+            "remove",
+            |rng| {
+                let xs = gen_vec(rng, 1, 20);
+                let i = rng.gen_range(0..xs.len());
+                (xs, i)
+            },
+            shrink_at,
+            |(xs, i)| RVec::from(xs.as_slice()).remove_requires(*i),
+            |(xs, i)| {
+                let mut v = RVec::from(xs.as_slice());
+                let x = v.remove(*i);
+                v.len() == xs.len() - 1 && x == xs[*i]
+            },
+        );
+    }
+
+    #[test]
+    fn prop_truncate() {
+        for_all(
+            "truncate",
+            |rng| (gen_vec(rng, 0, 20), rng.gen_range(0..30)),
+            shrink_at,
+            |_| true,
+            |(xs, k)| {
+                let mut v = RVec::from(xs.as_slice());
+                v.truncate(*k);
+                v.len() == (*k).min(xs.len())
+            },
+        );
+    }
+
+    #[test]
+    fn prop_split_off() {
+        for_all(
+            "split_off",
+            |rng| {
+                let xs = gen_vec(rng, 0, 20);
+                let at = rng.gen_range(0..=xs.len());
+                (xs, at)
+            },
+            shrink_at,
+            |(xs, at)| RVec::from(xs.as_slice()).split_off_requires(*at),
+            |(xs, at)| {
+                let mut v = RVec::from(xs.as_slice());
+                let w = v.split_off(*at);
+                v.len() == *at && w.len() == xs.len() - at
+            },
+        );
+    }
+
+    #[test]
+    fn fib_memo_fits_u64() {
+        let expected = [0, 1, 1, 2, 3, 5, 8, 13];
+        assert!((0..expected.len()).all(|n| fib_memo(n) == expected[n]));
+        assert_eq!(fib_memo(93), 12_200_160_415_121_876_738);
+    }
+
+    #[test]
+    fn lcs_known_answers() {
+        assert_eq!(lcs(b"", b"abc"), 0);
+        assert_eq!(lcs(b"abcde", b"ace"), 3);
+        assert_eq!(lcs(b"AGGTAB", b"GXTXAYB"), 4);
+    }
+
+    #[test]
+    fn knapsack_known_answers() {
+        assert_eq!(knapsack(&[], &[], 10), 0);
+        assert_eq!(knapsack(&[5], &[10], 4), 0);
+        assert_eq!(knapsack(&[1, 3, 4, 5], &[1, 4, 5, 7], 7), 9);
+        assert_eq!(knapsack(&[10, 20, 30], &[60, 100, 120], 50), 220);
+        assert_eq!(knapsack(&[1, 1], &[u64::MAX, 1], 2), u64::MAX);
+    }
+
+    #[test]
+    fn edit_distance_known_answers() {
+        assert_eq!(edit_distance(b"", b"abc"), 3);
+        assert_eq!(edit_distance(b"flux", b"flux"), 0);
+        assert_eq!(edit_distance(b"kitten", b"sitting"), 3);
+        assert_eq!(edit_distance(b"flaw", b"lawn"), 2);
+    }
+
+    /// Each sort must preserve the length, and produce the same
+    /// (sorted) elements as the standard library's sort.
+    fn check_sort(name: &str, sort: fn(RVec<i64>) -> SortedRVec<i64>) {
+        for_all(name, |rng| gen_vec(rng, 0, 30), shrink_ints, |_| true, |xs| {
+            let v = sort(RVec::from(xs.as_slice()));
+            let mut expected = xs.clone();
+            expected.sort();
+            v.len() == xs.len() && (0..v.len()).all(|i| *v.get(i) == expected[i])
+        });
+    }
+
+    #[test]
+    fn prop_sorts() {
+        check_sort("insertion_sort", insertion_sort);
+        check_sort("quicksort", quicksort);
+        check_sort("merge_sort", merge_sort);
+        for_all("selection_sort", |rng| gen_vec(rng, 0, 30), shrink_ints, |_| true, |xs| {
+            let mut v = RVec::from(xs.as_slice());
+            selection_sort(&mut v);
+            let mut expected = xs.clone();
+            expected.sort();
+            v.len() == xs.len() && v.as_slice() == expected.as_slice()
+        });
+    }
+
+    /// `merge` is a loop, so merging long vectors does not overflow the stack.
+    #[test]
+    fn merge_sort_large_input() {
+        let v = merge_sort(RVec::from((0..100_000i64).rev().collect::<Vec<_>>()));
+        assert_eq!(v.len(), 100_000);
+        assert!((0..v.len()).all(|i| *v.get(i) == i as i64));
+    }
+
+    #[test]
+    fn prop_binary_search() {
+        for_all(
+            "binary_search",
+            |rng| (gen_vec(rng, 0, 20), rng.gen_range(-100..100)),
+            |(xs, x)| shrink_pair(&(xs.clone(), *x), shrink_ints, shrink_i64),
+            |_| true,
+            |(xs, x)| {
+                let ys: Vec<i32> = xs.iter().map(|x| *x as i32).collect();
+                let v = insertion_sort(RVec::from(ys));
+                let x = *x as i32;
+                let at = |i: usize| *v.get(i);
+                v.len() == xs.len()
+                    && (1..v.len()).all(|i| at(i - 1) <= at(i))
+                    && match v.binary_search(&x) {
+                        Ok(i) => i < v.len() && at(i) == x,
+                        Err(i) => {
+                            i <= v.len()
+                                && (i == 0 || at(i - 1) < x)
+                                && (i == v.len() || x < at(i))
+                        }
+                    }
+            },
+        );
+    }
+
+    /// Pushes the non-negative numbers and pops on the negative ones, whenever
+    /// the `RingBuf` is not full (resp. empty), and compares with a `VecDeque`.
+    #[test]
+    fn prop_ring_buf_fifo() {
+        for_all(
+            "ring_buf",
+            |rng| (gen_vec(rng, 0, 50), rng.gen_range(1..8)),
+            shrink_at,
+            |(_, cap)| 0 < *cap,
+            |(ops, cap)| {
+                let mut buf = RingBuf::with_capacity(*cap);
+                let mut model = VecDeque::new();
+                for op in ops {
+                    if 0 <= *op && !buf.is_full() {
+                        buf.push_back(*op);
+                        model.push_back(*op);
+                    } else if *op < 0 && !buf.is_empty() {
+                        let x = buf.pop_front();
+                        if Some(x) != model.pop_front() {
+                            return false;
+                        }
+                    }
+                    let same = (0..buf.len()).all(|i| Some(buf.get(i)) == model.get(i));
+                    if buf.len() != model.len() || buf.len() > buf.capacity() || !same {
+                        return false;
+                    }
+                }
+                true
+            },
+        );
+    }
+}
@@ -3,6 +3,18 @@
 
 = Const Generics
 
+*/
+
+
+
+#![allow(unused)]
+extern crate flux_rs;
+use flux_rs::attrs::*;
+
+
+
+/*
+
 // [Online demo](https://flux.goto.ucsd.edu/index.html#?demo=arrays.rs)
 
 Rust has a built-in notion of _arrays_: collections of objects of
@@ -98,9 +110,24 @@ We can fix these errors by requiring that the input
 - `i` be a valid color index, i.e. `i < 3` and
 - `pixels` be non-empty, i.e. have size `n` where `0 < n`
 
-```rust
+*/
+
+
+
+pub type Pixel = [u8; 3]; // RGB values
+
 #[spec(fn(pixels: &[Pixel][@n], i:usize{i<3}) -> u64 requires 0 < n)]
-```
+pub fn average_color(pixels: &[Pixel], i: usize) -> u64 {
+    let mut sum = 0;
+    for p in pixels {
+        sum += p[i] as u64;
+    }
+    sum / pixels.len() as u64
+}
+
+
+
+/*
 
 #figure(
     image("../img/04-arrays-average-fix.gif", width: 90%),
@@ -189,16 +216,23 @@ fn dot_k<const N:usize>(x: [f32;N], y: [f32;N], k: usize) -> f32 {
 
 #strong[The Strict] approach is to require that `k` be less than or equal to `N`
 
-```rust
+*/
+
+
+
+#[contract]
 #[sig(fn(x: [f32;N], y: [f32;N], k:usize{k <= N}) -> f32)]
-fn dot_k<const N:usize>(x: [f32;N], y: [f32;N], k: usize) -> f32 {
+pub fn dot_k<const N:usize>(x: [f32;N], y: [f32;N], k: usize) -> f32 {
     let mut sum = 0.0;
     for i in 0..k {
         sum += x[i] * y[i];
     }
     sum
 }
-```
+
+
+
+/*
 
 #alert("success", [
 *EXERCISE:* Do you understand why in the code below (1)adding the type signature moved the error from the body of `dot_k` into the call-site inside `test`, and then (2) editing `test` to call `dot_k` with `k=2` _fixed_ the error?
@@ -9,6 +9,7 @@
 
 
 #![allow(unused)]
+#![allow(non_snake_case)]
 extern crate flux_rs;
 extern crate flux_core;
 extern crate flux_alloc;
@@ -608,6 +609,112 @@ impl <A> Index<Range<usize>> for Vec<A> {
 
 /*
 
+== Refining Operator Traits <ch:08_traits:operators>
+
+Recall that `x + y` is just `x.add(y)`, for the `add` method of the
+`std::ops::Add` trait. So to give a refined type to the `+` on some type,
+we implement `Add` with a refined `spec`. The implementation can _strengthen_
+the output, e.g. to say that the result is _exactly_ the sum of the inputs.
+But what if `+` only makes sense for _some_ inputs, e.g. those whose sum
+does not overflow? As described in the *Subtyping* note above, an
+implementation cannot _accept fewer_ inputs than the trait method,
+and the trait says nothing about which inputs are allowed!
+
+=== Allowed Operands
+
+We can use the same trick as for `valid` indexes. Here are _extern specs_
+(see @ch:07_externs) for the arithmetic operator traits, which add an
+associated refinement `allowed` that says which operands the operator
+may be applied to.
+
+*/
+
+
+
+#[extern_spec(std::ops)]
+#[assoc(fn allowed(me: Self, rhs: Rhs) -> bool { true })]
+trait Add<Rhs = Self> {
+    #[spec(fn(Self[@me], rhs: Rhs{Self::allowed(me, rhs)}) -> Self::Output)]
+    fn add(self, rhs: Rhs) -> Self::Output;
+}
+
+#[extern_spec(std::ops)]
+#[assoc(fn allowed(me: Self, rhs: Rhs) -> bool { true })]
+trait Sub<Rhs = Self> {
+    #[spec(fn(Self[@me], rhs: Rhs{Self::allowed(me, rhs)}) -> Self::Output)]
+    fn sub(self, rhs: Rhs) -> Self::Output;
+}
+
+#[extern_spec(std::ops)]
+#[assoc(fn allowed(me: Self, rhs: Rhs) -> bool { true })]
+trait Mul<Rhs = Self> {
+    #[spec(fn(Self[@me], rhs: Rhs{Self::allowed(me, rhs)}) -> Self::Output)]
+    fn mul(self, rhs: Rhs) -> Self::Output;
+}
+
+#[extern_spec(std::ops)]
+#[assoc(fn allowed(me: Self, rhs: Rhs) -> bool { true })]
+trait Div<Rhs = Self> {
+    #[spec(fn(Self[@me], rhs: Rhs{Self::allowed(me, rhs)}) -> Self::Output)]
+    fn div(self, rhs: Rhs) -> Self::Output;
+}
+
+#[extern_spec(std::ops)]
+#[assoc(fn allowed(me: Self, rhs: Rhs) -> bool { true })]
+trait Rem<Rhs = Self> {
+    #[spec(fn(Self[@me], rhs: Rhs{Self::allowed(me, rhs)}) -> Self::Output)]
+    fn rem(self, rhs: Rhs) -> Self::Output;
+}
+
+#[extern_spec(std::ops)]
+#[assoc(fn allowed(me: Self) -> bool { true })]
+trait Neg {
+    #[spec(fn(me: Self{Self::allowed(me)}) -> Self::Output)]
+    fn neg(self) -> Self::Output;
+}
+
+
+
+/*
+
+Unlike `valid`, the associated refinement `allowed` has a _default_ body,
+`true`, which says that every pair of operands is allowed. So existing
+implementations, that do not mention `allowed`, keep working as before.
+An implementation that needs a _precondition_ can override `allowed`,
+and then Flux checks that every use of the operator, say `x + y`,
+satisfies the precondition `Self::allowed(x, y)`. The unary `Neg`,
+i.e. `-x`, has just the one operand, so its `allowed` only takes `x`.
+
+=== Checked Arithmetic on Positive Integers
+
+For example, in @ch:03_structs:positive-arithmetic we implement
+the operators for `Positivei32`. Here is the implementation of `Add`.
+
+```rust
+#[assoc(fn allowed(a: int, b: int) -> bool { a + b <= 2147483647 })]
+impl Add for Positivei32 {
+  type Output = Positivei32;
+
+  #[spec(fn(Self[@a], b: Self{Self::allowed(a, b)}) -> Self[a + b])]
+  fn add(self, other: Positivei32) -> Positivei32 {
+    Positivei32 { val: self.val + other.val }
+  }
+}
+```
+
+The implementation overrides `allowed` to say that the sum must fit in
+an `i32`, which is the precondition Flux checks at each `+`, and in
+return, the `spec` says that the output is _exactly_ the sum of the inputs.
+As with `valid`, `Positivei32`s are represented by their value `n` at the
+refinement level, so `allowed` takes two `int`s as parameters.
+
+#alert("success", [
+*EXERCISE:* As `allowed` is `true` by default, Flux does not check the
+`+` on plain `i32`s for overflow. How would you write an extern spec for
+the implementation of `Add` for `i32` so that it does?
+])
+
+
 == Summary
 
 In this chapter, we saw how traits can be extended with *associated refinements*
@@ -615,6 +722,10 @@ which let us _declare_ refinements on the inputs and outputs of trait methods
 (e.g. `valid` indexes) that are then _implemented_  by each implementation of
 the trait (e.g. the index is less than the slice size).
 //
+With a default body, an associated refinement like `allowed` can even
+add preconditions to existing traits like `std::ops::Add`, without
+breaking the existing implementations.
+//
 Associated refinements turn out to be an extremely useful mechanism.
 //
 For example, they let us specify properties of commonly used operations like
@@ -3,5 +3,376 @@
 
 = Interpreters <ch:10_scope>
 
-#alert("error", [TODO])
 */
+
+
+
+#![allow(unused)]
+extern crate flux_rs;
+use flux_rs::attrs::*;
+use flux_rs::assert;
+
+
+
+/*
+
+Back in @ch:04_enums we wrote a `safe_divide` function that returns
+an `Err` instead of dividing by zero, and in @ch:07_externs we did the same
+for `safe_div`. Lets put these small pieces together to build something
+more substantial: an _interpreter_ for arithmetic expressions that never
+panics, neither by dividing by zero, nor by overflowing, and an optimization
+pass that is _proven_ not to change the meaning of an expression.
+
+== Arithmetic Expressions
+
+An expression is a number, or the sum, difference, product or quotient
+of two expressions, or the negation of one.
+
+```rust
+pub enum Expr {
+    Num(i32),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+}
+```
+
+Evaluating an expression can _fail_: the divisor may be `0`, or the
+result of an operation may not fit in an `i32`.
+
+=== Refining Expressions by their Values
+
+How do we specify what `eval` computes? We can _index_ each expression
+with its meaning, just like we indexed the `Timer` in @ch:04_enums by its
+remaining time. The meaning of an expression is a pair of
+
+- `val`, the (mathematical) value of the expression, and
+- `ok`, which is `true` when evaluating the expression does not fail,
+
+where `val` is only relevant when `ok` holds. An operation fails if either
+operand fails, or if its result is not `in_i32`, i.e. does not fit in an
+`i32`, as defined in @ch:externs. As Rust's `/` truncates towards zero,
+the value of a quotient is given by `trunc_div`, also from @ch:externs.
+
+*/
+
+
+
+#[refined_by(val: int, ok: bool)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    #[variant((i32[@n]) -> Expr[n, true])]
+    Num(i32),
+
+    #[variant((Box<Expr[@a, @oa]>, Box<Expr[@b, @ob]>) -> Expr[a + b, oa && ob && in_i32(a + b)])]
+    Add(Box<Expr>, Box<Expr>),
+
+    #[variant((Box<Expr[@a, @oa]>, Box<Expr[@b, @ob]>) -> Expr[a - b, oa && ob && in_i32(a - b)])]
+    Sub(Box<Expr>, Box<Expr>),
+
+    #[variant((Box<Expr[@a, @oa]>, Box<Expr[@b, @ob]>) -> Expr[a * b, oa && ob && in_i32(a * b)])]
+    Mul(Box<Expr>, Box<Expr>),
+
+    #[variant((Box<Expr[@a, @oa]>, Box<Expr[@b, @ob]>)
+              -> Expr[trunc_div(a, b), oa && ob && b != 0 && in_i32(trunc_div(a, b))])]
+    Div(Box<Expr>, Box<Expr>),
+
+    #[variant((Box<Expr[@a, @oa]>) -> Expr[-a, oa && in_i32(-a)])]
+    Neg(Box<Expr>),
+}
+
+
+
+/*
+
+For example, `Div(Num(-7), Num(2))` has the type `Expr[-3, true]`, while
+`Div(Num(7), Num(0))` has some type `Expr[v, false]`, as does
+`Neg(Num(-2147483648))`, whose value `2147483648` does not fit in an `i32`.
+
+#alert("info", [
+*Indices are Specifications:* Notice that the indices give a complete
+specification of evaluation: they say exactly which value each
+expression has, and exactly which expressions fail. The `variant`
+attributes are, in effect, a _definitional interpreter_ that runs
+at the refinement level.
+])
+
+== A Safe Evaluator
+
+To avoid overflows, the evaluator computes each operation on `i64`s,
+where the result of an operation on two `i32`s always fits, and then
+checks whether the result fits back into an `i32`.
+
+=== Checking the Result Fits
+
+The check is done by `narrow`, which returns a valid `Option` exactly
+when the `i64` fits in an `i32`. Flux does not know the value of a
+narrowing `as` cast, so, like the `RVec` API in @ch:05_vectors, we mark
+`narrow` as `trusted` and implement it with `i32::try_from`, which does
+the check at run-time.
+
+*/
+
+
+
+#[trusted]
+#[spec(fn(n: i64) -> Option<i32[n]>[in_i32(n)])]
+fn narrow(n: i64) -> Option<i32> {
+    i32::try_from(n).ok()
+}
+
+
+
+/*
+
+=== Evaluating Expressions
+
+Now `eval` returns an `Option<i32[val]>` that is valid exactly when
+the expression is `ok`. Each arm evaluates the operands, and then
+`narrow`s the result, except for `Div`, which uses the `checked_div`
+from @ch:externs, whose `spec` says that it fails exactly when the
+divisor is `0` (or the quotient does not fit), and otherwise returns
+the `trunc_div` of the operands, just like the index of a `Div`.
+
+*/
+
+
+
+impl Expr {
+    #[spec(fn(&Expr[@val, @ok]) -> Option<i32[val]>[ok])]
+    pub fn eval(&self) -> Option<i32> {
+        match self {
+            Expr::Num(n) => Some(*n),
+            Expr::Add(a, b) => match (a.eval(), b.eval()) {
+                (Some(x), Some(y)) => narrow(x as i64 + y as i64),
+                _ => None,
+            },
+            Expr::Sub(a, b) => match (a.eval(), b.eval()) {
+                (Some(x), Some(y)) => narrow(x as i64 - y as i64),
+                _ => None,
+            },
+            Expr::Mul(a, b) => match (a.eval(), b.eval()) {
+                (Some(x), Some(y)) => narrow(x as i64 * y as i64),
+                _ => None,
+            },
+            Expr::Div(a, b) => match (a.eval(), b.eval()) {
+                (Some(x), Some(y)) => x.checked_div(y),
+                _ => None,
+            },
+            Expr::Neg(a) => match a.eval() {
+                Some(x) => narrow(-(x as i64)),
+                None => None,
+            },
+        }
+    }
+}
+
+
+
+/*
+
+Flux checks that each arm returns exactly the value of the expression,
+and that it returns `None` only when the index says the expression fails.
+The evaluator cannot panic: it never divides with `/`, and, if you turn on
+Flux's overflow checking, the `i64` operations are safe as their operands
+are `i32`s.
+
+=== Using the Evaluator
+
+As `eval` is completely specified, Flux can compute the result of
+evaluating a _known_ expression at compile-time, and hence, lets us
+`unwrap` the results of expressions that do not fail.
+
+*/
+
+
+
+fn num(n: i32) -> Box<Expr> {
+    Box::new(Expr::Num(n))
+}
+
+fn test_eval() {
+    let e = Expr::Add(num(1), Box::new(Expr::Mul(num(2), num(3))));
+    assert(e.eval().unwrap() == 7);
+
+    let d = Expr::Div(num(-7), num(2));
+    assert(d.eval().unwrap() == -3);            // rounds towards zero
+
+    let z = Expr::Div(num(7), Box::new(Expr::Sub(num(2), num(2))));
+    assert(z.eval().is_none());                 // division by zero
+
+    let o = Expr::Neg(num(-2147483648));
+    assert(o.eval().is_none());                 // overflow
+}
+
+
+
+/*
+
+#alert("success", [
+*EXERCISE:* What happens if you `unwrap` the result of evaluating
+`Add(num(2147483647), num(1))` in `test_eval`? Why?
+])
+
+== Constant Folding
+
+An _optimizer_ rewrites a program into an equivalent, but cheaper one.
+One of the simplest optimizations is _constant folding_, which replaces
+sub-expressions whose values can be computed ahead of time with their values.
+
+=== Folding Expressions
+
+The function `const_fold` first folds the operands of an expression,
+and then, if the resulting expression can be evaluated, replaces it
+with a `Num`. Sub-expressions that _fail_ cannot be replaced, but their
+operands may still be folded. For example, `Add(Mul(2, 3), Div(1, 0))`
+is folded to `Add(6, Div(1, 0))`.
+
+*/
+
+
+
+impl Expr {
+    #[spec(fn(Expr[@val, @ok]) -> Expr[val, ok])]
+    pub fn const_fold(self) -> Expr {
+        let e = match self {
+            Expr::Num(n) => Expr::Num(n),
+            Expr::Add(a, b) => Expr::Add(Box::new(a.const_fold()), Box::new(b.const_fold())),
+            Expr::Sub(a, b) => Expr::Sub(Box::new(a.const_fold()), Box::new(b.const_fold())),
+            Expr::Mul(a, b) => Expr::Mul(Box::new(a.const_fold()), Box::new(b.const_fold())),
+            Expr::Div(a, b) => Expr::Div(Box::new(a.const_fold()), Box::new(b.const_fold())),
+            Expr::Neg(a) => Expr::Neg(Box::new(a.const_fold())),
+        };
+        match e.eval() {
+            Some(n) => Expr::Num(n),
+            None => e,
+        }
+    }
+}
+
+
+
+/*
+
+=== Folding Preserves Meaning
+
+The `spec` says that `const_fold` returns an expression with _the same_
+indices as its input. As the `spec` of `eval` is determined by the indices,
+this means that `eval` returns the same result on the folded expression as
+on the original. In other words, Flux _proves_ that constant folding
+preserves the meaning of every expression!
+
+Why does Flux accept `const_fold`? The recursive calls fold the operands
+into expressions with the same indices, so rebuilding the node with
+the folded operands preserves the indices. Finally, when `e.eval()`
+returns `Some(n)`, Flux knows that `ok` holds and that `n` is `val`,
+so `Num(n)` also has the type `Expr[val, ok]`.
+
+*/
+
+
+
+fn test_const_fold() {
+    let e = Expr::Add(Box::new(Expr::Mul(num(2), num(3))), Box::new(Expr::Div(num(1), num(0))));
+    let f = e.clone().const_fold();
+    assert(f.eval().is_none());                 // still fails, like `e`
+    let g = Expr::Sub(num(10), Box::new(Expr::Neg(num(4)))).const_fold();
+    assert(g.eval().unwrap() == 14);            // g: Expr[14, true]
+}
+
+
+
+/*
+
+#alert("success", [
+*EXERCISE:* Another simple optimization replaces `Mul(e, Num(0))`
+with `Num(0)`. Write a function `simplify` with the same `spec` as
+`const_fold` that does this rewrite. Does Flux accept it? Why not?
+(_Hint:_ What if evaluating `e` fails?)
+])
+
+== Summary
+
+In this chapter we used refined `enum`s to give an arithmetic expression
+language a complete specification: each expression is indexed by its
+value, and by whether evaluating it fails. Flux then checked that
+
+- `eval` computes exactly the value of an expression, returning `None`
+  only on division by zero or overflow, without ever panicking, and
+- `const_fold` preserves the value of every expression.
+
+*/
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::props::*;
+    use rand::Rng;
+    use rand::rngs::StdRng;
+
+    /// A random expression of at most `depth` levels, whose numbers are often
+    /// `0` or close to the bounds of `i32`, so that evaluation often fails.
+    fn gen_expr(rng: &mut StdRng, depth: usize) -> Expr {
+        if depth == 0 || rng.gen_range(0..4) == 0 {
+            let n = match rng.gen_range(0..6) {
+                0 => 0,
+                1 => i32::MAX - rng.gen_range(0..3),
+                2 => i32::MIN + rng.gen_range(0..3),
+                3 => rng.gen_range(-2..3),
+                _ => rng.gen_range(-100_000..100_000),
+            };
+            return Expr::Num(n);
+        }
+        let a = Box::new(gen_expr(rng, depth - 1));
+        let b = Box::new(gen_expr(rng, depth - 1));
+        match rng.gen_range(0..5) {
+            0 => Expr::Add(a, b),
+            1 => Expr::Sub(a, b),
+            2 => Expr::Mul(a, b),
+            3 => Expr::Div(a, b),
+            _ => Expr::Neg(a),
+        }
+    }
+
+    /// An expression shrinks to its operands, and a number towards `0`.
+    fn shrink_expr(e: &Expr) -> Vec<Expr> {
+        match e {
+            Expr::Num(n) => shrink_i64(&(*n as i64)).into_iter().map(|n| Expr::Num(n as i32)).collect(),
+            Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) | Expr::Div(a, b) => {
+                vec![(**a).clone(), (**b).clone()]
+            }
+            Expr::Neg(a) => vec![(**a).clone()],
+        }
+    }
+
+    /// The reference semantics, using the `checked` operations from `std`.
+    fn checked_eval(e: &Expr) -> std::option::Option<i32> {
+        match e {
+            Expr::Num(n) => Some(*n),
+            Expr::Add(a, b) => checked_eval(a)?.checked_add(checked_eval(b)?),
+            Expr::Sub(a, b) => checked_eval(a)?.checked_sub(checked_eval(b)?),
+            Expr::Mul(a, b) => checked_eval(a)?.checked_mul(checked_eval(b)?),
+            Expr::Div(a, b) => checked_eval(a)?.checked_div(checked_eval(b)?),
+            Expr::Neg(a) => checked_eval(a)?.checked_neg(),
+        }
+    }
+
+    #[test]
+    fn prop_eval() {
+        for_all("eval", |rng| gen_expr(rng, 5), shrink_expr, |_| true, |e| {
+            e.eval() == checked_eval(e)
+        });
+    }
+
+    #[test]
+    fn prop_const_fold() {
+        for_all("const_fold", |rng| gen_expr(rng, 5), shrink_expr, |_| true, |e| {
+            let f = e.clone().const_fold();
+            f.eval() == e.eval() && matches!(f, Expr::Num(_)) == e.eval().is_some()
+        });
+    }
+}
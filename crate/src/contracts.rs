@@ -0,0 +1,137 @@
+/*
+#import "../orly-modified.typ": alert
+
+= Runtime Contracts <ch:contracts>
+
+Flux checks `spec`s at compile time, but not every build of this crate runs
+Flux, and not every module is checked (see the `include` list in `Cargo.toml`).
+The `#[contract]` attribute, defined in the `spec-contracts` crate (in the
+`contracts` directory), lets us _also_ check a function's `spec` at run time,
+as `debug_assert!`s that are _generated_ from the `spec` itself, so that the
+checks cannot drift out of sync with it. For example,
+
+```rust
+#[contract]
+#[spec(fn(&Self[@r], k: i32{r.start <= k && k <= r.end}) -> (Self[r.start, k], Self[k, r.end]))]
+pub fn split_at(&self, k: i32) -> (Range, Range) { ... }
+```
+
+checks the _preconditions_, i.e. the refinements of the parameters and the
+`requires` clause, on entry, and the _postconditions_, i.e. the refinements
+`{v: ...}` of the result, on exit. The `#[contract]` must come _before_ the
+`#[spec]`, so that it can read it.
+
+The refinement variable `r` stands for the parameter it is bound on, here
+`self`, so `r.start` is checked as `self.start`. A refinement on the payload
+of an `Option` result, as in the `spec` of `safe_div` from @ch:07_externs,
+is checked whenever the result is a `Some`. When an index is not a field
+of the parameter, we tell `#[contract]` how to compute it, e.g.
+`#[contract(n = self.len())]` for the `pop` method of @ch:05_vectors.
+The _indices_ of the result, e.g. `Self[r.start, k]` above, and `ensures`
+clauses are not checked, as they are about values we cannot (in general)
+compute at run time. The exception is the index of a _primitive_ result, like
+the `usize[s + k]` of `add_points` in @ch:01_refinements, which is checked when
+it only uses arithmetic that means the same in Rust as in the logic.
+//
+In test builds, `#[contract]` also generates the predicates `f_requires` and
+`f_ensures` that the property tests of @ch:props use.
+
+The checks are opt-in: they are only compiled when the crate is built with the
+`runtime-contracts` feature, for example, via
+
+```
+cargo test --features runtime-contracts
+```
+
+Otherwise, `#[contract]` leaves the function unchanged, and so has no effect
+on the code that Flux sees.
+
+Each clause is checked separately, so when a check fails, the panic message
+(and location) points to the _particular_ clause that was violated. For example,
+calling `split_at(15)` on a `Range` from `0` to `10` panics with
+
+```
+precondition violated: `k <= r.end`
+```
+
+The contracts are also checked when the code is _wrong_, which is handy for
+the exercises: for example, the buggy `factorial` of @ch:01_refinements
+panics with
+
+```
+postcondition violated: `1 <= v`
+```
+
+until you fix it, while the `new` constructor of `Range` from
+@ch:03_structs panics when given an input its `spec` rules out.
+
+*/
+
+
+
+#[cfg(all(test, feature = "runtime-contracts"))]
+mod tests {
+    use crate::ch01_refinements::abs;
+    use crate::ch03_structs::{Positivei32, Range};
+    use crate::ch05_vectors::RVec;
+    use flux_rs::attrs::*;
+
+    /// `safe_div` from `ch07_externs`, which is not built by `cargo` (it needs
+    /// the nightly `allocator_api`), and a copy that breaks its contract.
+    #[contract]
+    #[spec(fn (num: u32, denom: u32) -> Option<u32{v: v == num / denom}>)]
+    fn safe_div(num: u32, denom: u32) -> Option<u32> {
+        num.checked_div(denom)
+    }
+
+    #[contract]
+    #[spec(fn (num: u32, denom: u32) -> Option<u32{v: v == num / denom}>)]
+    fn rounding_div(num: u32, denom: u32) -> Option<u32> {
+        if denom == 0 { None } else { Some(num.div_ceil(denom)) }
+    }
+
+    #[test]
+    fn contracts_hold() {
+        assert_eq!(abs(-5), 5);
+        assert_eq!(safe_div(7, 2), Some(3));
+        assert_eq!(safe_div(7, 0), None);
+        assert_eq!(Positivei32::new(1).map(|p| p.get()), Some(1));
+        assert_eq!(Positivei32::new(0), None);
+        assert_eq!(Range::new(0, 10).clamp(12), 9);
+        let mut v = RVec::new();
+        v.push(1);
+        assert_eq!(v.pop(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "postcondition violated: `v == num / denom`")]
+    fn rounding_div_breaks_its_contract() {
+        assert_eq!(rounding_div(6, 3), Some(2));
+        rounding_div(7, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "precondition violated: `start <= end`")]
+    fn range_new_requires_start_before_end() {
+        Range::new(10, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "precondition violated: `k <= r.end`")]
+    fn split_at_requires_k_in_range() {
+        Range::new(0, 10).split_at(15);
+    }
+
+    #[test]
+    #[should_panic(expected = "precondition violated: `r.start < r.end`")]
+    fn clamp_requires_non_empty() {
+        Range::new(3, 3).clamp(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "precondition violated: `0 < n`")]
+    fn pop_requires_non_empty() {
+        let mut v: RVec<i32> = RVec::new();
+        v.pop();
+    }
+}
@@ -0,0 +1,671 @@
+/*
+#import "../orly-modified.typ": alert
+
+= Extern Specs for the Standard Library <ch:externs>
+
+*/
+
+
+
+#![allow(unused)]
+extern crate flux_rs;
+use flux_rs::{attrs::*, assert, extern_spec};
+
+
+
+/*
+
+In @ch:07_externs we saw how `extern_spec` lets us attach refined
+specifications to functions, types and methods defined _elsewhere_,
+e.g. in the standard library. That chapter is a tour, with exercises
+where the specs are deliberately left for you to fix. This module
+collects the (finished) extern specs that the other chapters and
+modules rely on, so that Flux checks them along with the code that
+uses them.
+
+== Integer Methods
+
+Back in @ch:01_refinements and @ch:04_enums we blithely ignored the possibility
+of arithmetic _overflow_. The standard library offers a family of methods that
+deal with overflow explicitly, by returning an `Option` (`checked_*`), clamping
+the result (`saturating_*`), or wrapping around (`wrapping_*`). Lets write
+extern specs that say _exactly_ what each of these methods returns.
+
+=== Describing the Ranges
+
+First, lets define refinement functions that describe the range of values
+of each integer type, and what it means to wrap an integer into a range.
+(For `usize` we assume a 64-bit target.) To clamp an integer into a range,
+we reuse `clamp` from @ch:math.
+
+*/
+
+
+
+defs! {
+    fn in_i32(x: int) -> bool { -2147483648 <= x && x <= 2147483647 }
+    fn in_i64(x: int) -> bool { -9223372036854775808 <= x && x <= 9223372036854775807 }
+    fn in_u32(x: int) -> bool { 0 <= x && x <= 4294967295 }
+    fn in_u64(x: int) -> bool { 0 <= x && x <= 18446744073709551615 }
+    fn in_usize(x: int) -> bool { 0 <= x && x <= 18446744073709551615 }
+
+    fn wrap(x: int, lo: int, hi: int) -> int {
+        lo + (x - lo) % (hi - lo + 1)
+    }
+
+    fn abs(x: int) -> int {
+        if x < 0 { 0 - x } else { x }
+    }
+}
+
+
+
+/*
+
+=== Rounding Division
+
+There is one more subtlety. In the refinement logic, as in most SMT solvers,
+`a / b` and `a % b` are _Euclidean_: the remainder `a % b` is never negative.
+Rust's `/` on signed integers instead _truncates_ towards zero, so the two
+disagree when `a` is negative: `-7 / 2` is `-3` in Rust, but `-4` in the logic.
+(They agree whenever `a` and `b` are both non-negative.) So for signed
+division, we describe the Rust result with `trunc_div`, which divides the
+absolute values and then fixes up the sign.
+
+*/
+
+
+
+defs! {
+    fn trunc_div(a: int, b: int) -> int {
+        if (0 <= a && 0 < b) || (a < 0 && b < 0) { abs(a) / abs(b) } else { 0 - abs(a) / abs(b) }
+    }
+}
+
+
+
+/*
+
+=== Specs for `i32` and `i64`
+
+Now, the `checked_*` methods return a valid `Option` exactly when
+the mathematical result is in range, in which case the payload _is_
+the mathematical result. The `saturating_*` and `wrapping_*` methods
+always return the clamped or wrapped mathematical result.
+//
+The remainder of `rem_euclid` is never negative, which is _exactly_
+the `%` of the logic. Like `/`, it panics when the quotient overflows,
+so the signed versions _require_ that we do not divide `MIN` by `-1`.
+And `pow` _requires_ that the power, as described by the `pow` from
+@ch:math, fits in the type. Finally,
+`abs_diff` returns the distance between two numbers as an _unsigned_
+integer, which, unlike `(a - b).abs()`, always fits.
+
+*/
+
+
+
+#[extern_spec]
+impl i32 {
+    #[spec(fn(a: i32, b: i32) -> Option<i32[a + b]>[in_i32(a + b)])]
+    const fn checked_add(self, rhs: i32) -> Option<i32>;
+
+    #[spec(fn(a: i32, b: i32) -> Option<i32[a - b]>[in_i32(a - b)])]
+    const fn checked_sub(self, rhs: i32) -> Option<i32>;
+
+    #[spec(fn(a: i32, b: i32) -> Option<i32[a * b]>[in_i32(a * b)])]
+    const fn checked_mul(self, rhs: i32) -> Option<i32>;
+
+    #[spec(fn(a: i32, b: i32) -> Option<i32[trunc_div(a, b)]>[b != 0 && in_i32(trunc_div(a, b))])]
+    const fn checked_div(self, rhs: i32) -> Option<i32>;
+
+    #[spec(fn(a: i32, b: i32) -> i32[clamp(a + b, -2147483648, 2147483647)])]
+    const fn saturating_add(self, rhs: i32) -> i32;
+
+    #[spec(fn(a: i32, b: i32) -> i32[clamp(a - b, -2147483648, 2147483647)])]
+    const fn saturating_sub(self, rhs: i32) -> i32;
+
+    #[spec(fn(a: i32, b: i32) -> i32[clamp(a * b, -2147483648, 2147483647)])]
+    const fn saturating_mul(self, rhs: i32) -> i32;
+
+    #[spec(fn(a: i32, b: i32) -> i32[wrap(a + b, -2147483648, 2147483647)])]
+    const fn wrapping_add(self, rhs: i32) -> i32;
+
+    #[spec(fn(a: i32, b: i32) -> i32[wrap(a - b, -2147483648, 2147483647)])]
+    const fn wrapping_sub(self, rhs: i32) -> i32;
+
+    #[spec(fn(a: i32, b: i32) -> i32[wrap(a * b, -2147483648, 2147483647)])]
+    const fn wrapping_mul(self, rhs: i32) -> i32;
+
+    #[spec(fn(a: i32{a != -2147483648}) -> i32[abs(a)])]
+    const fn abs(self) -> i32;
+
+    #[spec(fn(a: i32, b: i32) -> u32[abs(a - b)])]
+    const fn abs_diff(self, other: i32) -> u32;
+
+    #[spec(fn(a: i32, e: u32) -> i32[pow(a, e)] requires in_i32(pow(a, e)))]
+    const fn pow(self, exp: u32) -> i32;
+
+    #[spec(fn(a: i32, b: i32{b != 0}) -> i32[a % b] requires !(a == -2147483648 && b == -1))]
+    const fn rem_euclid(self, rhs: i32) -> i32;
+}
+
+#[extern_spec]
+impl i64 {
+    #[spec(fn(a: i64, b: i64) -> Option<i64[a + b]>[in_i64(a + b)])]
+    const fn checked_add(self, rhs: i64) -> Option<i64>;
+
+    #[spec(fn(a: i64, b: i64) -> Option<i64[a - b]>[in_i64(a - b)])]
+    const fn checked_sub(self, rhs: i64) -> Option<i64>;
+
+    #[spec(fn(a: i64, b: i64) -> Option<i64[a * b]>[in_i64(a * b)])]
+    const fn checked_mul(self, rhs: i64) -> Option<i64>;
+
+    #[spec(fn(a: i64, b: i64) -> Option<i64[trunc_div(a, b)]>[b != 0 && in_i64(trunc_div(a, b))])]
+    const fn checked_div(self, rhs: i64) -> Option<i64>;
+
+    #[spec(fn(a: i64, b: i64) -> i64[clamp(a + b, -9223372036854775808, 9223372036854775807)])]
+    const fn saturating_add(self, rhs: i64) -> i64;
+
+    #[spec(fn(a: i64, b: i64) -> i64[clamp(a - b, -9223372036854775808, 9223372036854775807)])]
+    const fn saturating_sub(self, rhs: i64) -> i64;
+
+    #[spec(fn(a: i64, b: i64) -> i64[clamp(a * b, -9223372036854775808, 9223372036854775807)])]
+    const fn saturating_mul(self, rhs: i64) -> i64;
+
+    #[spec(fn(a: i64, b: i64) -> i64[wrap(a + b, -9223372036854775808, 9223372036854775807)])]
+    const fn wrapping_add(self, rhs: i64) -> i64;
+
+    #[spec(fn(a: i64, b: i64) -> i64[wrap(a - b, -9223372036854775808, 9223372036854775807)])]
+    const fn wrapping_sub(self, rhs: i64) -> i64;
+
+    #[spec(fn(a: i64, b: i64) -> i64[wrap(a * b, -9223372036854775808, 9223372036854775807)])]
+    const fn wrapping_mul(self, rhs: i64) -> i64;
+
+    #[spec(fn(a: i64{a != -9223372036854775808}) -> i64[abs(a)])]
+    const fn abs(self) -> i64;
+
+    #[spec(fn(a: i64, b: i64) -> u64[abs(a - b)])]
+    const fn abs_diff(self, other: i64) -> u64;
+
+    #[spec(fn(a: i64, e: u32) -> i64[pow(a, e)] requires in_i64(pow(a, e)))]
+    const fn pow(self, exp: u32) -> i64;
+
+    #[spec(fn(a: i64, b: i64{b != 0}) -> i64[a % b] requires !(a == -9223372036854775808 && b == -1))]
+    const fn rem_euclid(self, rhs: i64) -> i64;
+}
+
+
+
+/*
+
+#alert("info", [
+*Non-linear Arithmetic:* The specs for `pow`, `checked_div` and the
+`wrapping_*` methods are exact, but they use `pow`, `/` and `%` with
+operands that need not be constants. Reasoning about such _non-linear_
+arithmetic is undecidable in general, so Flux will only be able to make
+use of these specs when (at least) one of the operands is a known constant.
+])
+
+=== Specs for `u32`, `u64` and `usize`
+
+The unsigned types are much the same, except that their ranges start
+at `0` and they have no `abs`. As both operands are non-negative, the
+Rust `/` agrees with the `/` of the logic. Finally, `is_multiple_of` is the
+panic-free way to ask whether `b` divides `a`: unlike `a % b == 0`, it is
+defined even when `b` is `0`, and only `0` is a multiple of `0`.
+
+*/
+
+
+
+#[extern_spec]
+impl u32 {
+    #[spec(fn(a: u32, b: u32) -> Option<u32[a + b]>[in_u32(a + b)])]
+    const fn checked_add(self, rhs: u32) -> Option<u32>;
+
+    #[spec(fn(a: u32, b: u32) -> Option<u32[a - b]>[in_u32(a - b)])]
+    const fn checked_sub(self, rhs: u32) -> Option<u32>;
+
+    #[spec(fn(a: u32, b: u32) -> Option<u32[a * b]>[in_u32(a * b)])]
+    const fn checked_mul(self, rhs: u32) -> Option<u32>;
+
+    #[spec(fn(a: u32, b: u32) -> Option<u32[a / b]>[b != 0])]
+    const fn checked_div(self, rhs: u32) -> Option<u32>;
+
+    #[spec(fn(a: u32, b: u32) -> u32[clamp(a + b, 0, 4294967295)])]
+    const fn saturating_add(self, rhs: u32) -> u32;
+
+    #[spec(fn(a: u32, b: u32) -> u32[clamp(a - b, 0, 4294967295)])]
+    const fn saturating_sub(self, rhs: u32) -> u32;
+
+    #[spec(fn(a: u32, b: u32) -> u32[clamp(a * b, 0, 4294967295)])]
+    const fn saturating_mul(self, rhs: u32) -> u32;
+
+    #[spec(fn(a: u32, b: u32) -> u32[wrap(a + b, 0, 4294967295)])]
+    const fn wrapping_add(self, rhs: u32) -> u32;
+
+    #[spec(fn(a: u32, b: u32) -> u32[wrap(a - b, 0, 4294967295)])]
+    const fn wrapping_sub(self, rhs: u32) -> u32;
+
+    #[spec(fn(a: u32, b: u32) -> u32[wrap(a * b, 0, 4294967295)])]
+    const fn wrapping_mul(self, rhs: u32) -> u32;
+
+    #[spec(fn(a: u32, e: u32) -> u32[pow(a, e)] requires in_u32(pow(a, e)))]
+    const fn pow(self, exp: u32) -> u32;
+
+    #[spec(fn(a: u32, b: u32{b != 0}) -> u32[a % b])]
+    const fn rem_euclid(self, rhs: u32) -> u32;
+
+    #[spec(fn(a: u32, b: u32) -> bool[if b == 0 { a == 0 } else { a % b == 0 }])]
+    const fn is_multiple_of(self, rhs: u32) -> bool;
+}
+
+#[extern_spec]
+impl u64 {
+    #[spec(fn(a: u64, b: u64) -> Option<u64[a + b]>[in_u64(a + b)])]
+    const fn checked_add(self, rhs: u64) -> Option<u64>;
+
+    #[spec(fn(a: u64, b: u64) -> Option<u64[a - b]>[in_u64(a - b)])]
+    const fn checked_sub(self, rhs: u64) -> Option<u64>;
+
+    #[spec(fn(a: u64, b: u64) -> Option<u64[a * b]>[in_u64(a * b)])]
+    const fn checked_mul(self, rhs: u64) -> Option<u64>;
+
+    #[spec(fn(a: u64, b: u64) -> Option<u64[a / b]>[b != 0])]
+    const fn checked_div(self, rhs: u64) -> Option<u64>;
+
+    #[spec(fn(a: u64, b: u64) -> u64[clamp(a + b, 0, 18446744073709551615)])]
+    const fn saturating_add(self, rhs: u64) -> u64;
+
+    #[spec(fn(a: u64, b: u64) -> u64[clamp(a - b, 0, 18446744073709551615)])]
+    const fn saturating_sub(self, rhs: u64) -> u64;
+
+    #[spec(fn(a: u64, b: u64) -> u64[clamp(a * b, 0, 18446744073709551615)])]
+    const fn saturating_mul(self, rhs: u64) -> u64;
+
+    #[spec(fn(a: u64, b: u64) -> u64[wrap(a + b, 0, 18446744073709551615)])]
+    const fn wrapping_add(self, rhs: u64) -> u64;
+
+    #[spec(fn(a: u64, b: u64) -> u64[wrap(a - b, 0, 18446744073709551615)])]
+    const fn wrapping_sub(self, rhs: u64) -> u64;
+
+    #[spec(fn(a: u64, b: u64) -> u64[wrap(a * b, 0, 18446744073709551615)])]
+    const fn wrapping_mul(self, rhs: u64) -> u64;
+
+    #[spec(fn(a: u64, e: u32) -> u64[pow(a, e)] requires in_u64(pow(a, e)))]
+    const fn pow(self, exp: u32) -> u64;
+
+    #[spec(fn(a: u64, b: u64{b != 0}) -> u64[a % b])]
+    const fn rem_euclid(self, rhs: u64) -> u64;
+
+    #[spec(fn(a: u64, b: u64) -> bool[if b == 0 { a == 0 } else { a % b == 0 }])]
+    const fn is_multiple_of(self, rhs: u64) -> bool;
+}
+
+#[extern_spec]
+impl usize {
+    #[spec(fn(a: usize, b: usize) -> Option<usize[a + b]>[in_usize(a + b)])]
+    const fn checked_add(self, rhs: usize) -> Option<usize>;
+
+    #[spec(fn(a: usize, b: usize) -> Option<usize[a - b]>[in_usize(a - b)])]
+    const fn checked_sub(self, rhs: usize) -> Option<usize>;
+
+    #[spec(fn(a: usize, b: usize) -> Option<usize[a * b]>[in_usize(a * b)])]
+    const fn checked_mul(self, rhs: usize) -> Option<usize>;
+
+    #[spec(fn(a: usize, b: usize) -> Option<usize[a / b]>[b != 0])]
+    const fn checked_div(self, rhs: usize) -> Option<usize>;
+
+    #[spec(fn(a: usize, b: usize) -> usize[clamp(a + b, 0, 18446744073709551615)])]
+    const fn saturating_add(self, rhs: usize) -> usize;
+
+    #[spec(fn(a: usize, b: usize) -> usize[clamp(a - b, 0, 18446744073709551615)])]
+    const fn saturating_sub(self, rhs: usize) -> usize;
+
+    #[spec(fn(a: usize, b: usize) -> usize[clamp(a * b, 0, 18446744073709551615)])]
+    const fn saturating_mul(self, rhs: usize) -> usize;
+
+    #[spec(fn(a: usize, b: usize) -> usize[wrap(a + b, 0, 18446744073709551615)])]
+    const fn wrapping_add(self, rhs: usize) -> usize;
+
+    #[spec(fn(a: usize, b: usize) -> usize[wrap(a - b, 0, 18446744073709551615)])]
+    const fn wrapping_sub(self, rhs: usize) -> usize;
+
+    #[spec(fn(a: usize, b: usize) -> usize[wrap(a * b, 0, 18446744073709551615)])]
+    const fn wrapping_mul(self, rhs: usize) -> usize;
+
+    #[spec(fn(a: usize, e: u32) -> usize[pow(a, e)] requires in_usize(pow(a, e)))]
+    const fn pow(self, exp: u32) -> usize;
+
+    #[spec(fn(a: usize, b: usize{b != 0}) -> usize[a % b])]
+    const fn rem_euclid(self, rhs: usize) -> usize;
+}
+
+
+
+/*
+
+=== Specs for `min` and `max`
+
+Unlike the above, `min` and `max` are not inherent methods of the
+integer types, but are _provided_ methods of the `Ord` trait. So we
+write their extern specs on the `Ord` implementations for each type.
+
+*/
+
+
+
+#[extern_spec]
+impl Ord for i32 {
+    #[spec(fn(a: i32, b: i32) -> i32[if a < b { a } else { b }])]
+    fn min(self, other: i32) -> i32;
+
+    #[spec(fn(a: i32, b: i32) -> i32[if a < b { b } else { a }])]
+    fn max(self, other: i32) -> i32;
+}
+
+#[extern_spec]
+impl Ord for i64 {
+    #[spec(fn(a: i64, b: i64) -> i64[if a < b { a } else { b }])]
+    fn min(self, other: i64) -> i64;
+
+    #[spec(fn(a: i64, b: i64) -> i64[if a < b { b } else { a }])]
+    fn max(self, other: i64) -> i64;
+}
+
+#[extern_spec]
+impl Ord for u32 {
+    #[spec(fn(a: u32, b: u32) -> u32[if a < b { a } else { b }])]
+    fn min(self, other: u32) -> u32;
+
+    #[spec(fn(a: u32, b: u32) -> u32[if a < b { b } else { a }])]
+    fn max(self, other: u32) -> u32;
+}
+
+#[extern_spec]
+impl Ord for u64 {
+    #[spec(fn(a: u64, b: u64) -> u64[if a < b { a } else { b }])]
+    fn min(self, other: u64) -> u64;
+
+    #[spec(fn(a: u64, b: u64) -> u64[if a < b { b } else { a }])]
+    fn max(self, other: u64) -> u64;
+}
+
+#[extern_spec]
+impl Ord for usize {
+    #[spec(fn(a: usize, b: usize) -> usize[if a < b { a } else { b }])]
+    fn min(self, other: usize) -> usize;
+
+    #[spec(fn(a: usize, b: usize) -> usize[if a < b { b } else { a }])]
+    fn max(self, other: usize) -> usize;
+}
+
+
+
+/*
+
+=== Overflow-Free Factorial
+
+Lets use these specs to write a version of the `factorial` from @ch:01_refinements
+that _cannot_ overflow: it uses `checked_mul`, and bails out with `None` as soon
+as the product no longer fits in an `i32`.
+
+*/
+
+
+
+#[spec(fn(n: i32{0 <= n}) -> Option<i32{v: 1 <= v && n <= v}>)]
+pub fn checked_factorial(n: i32) -> Option<i32> {
+    let mut i = 0;
+    let mut res: i32 = 1;
+    while i < n {
+        i += 1;
+        res = res.checked_mul(i)?;
+    }
+    Some(res)
+}
+
+
+
+/*
+
+The specs are also precise enough to check concrete computations,
+including the rounding of division on negative numbers.
+
+*/
+
+
+
+fn test_int_specs() {
+    assert(7i32.checked_add(8).unwrap() == 15);
+    assert(2147483647i32.checked_add(1).is_none());
+    assert((-7i32).checked_div(2).unwrap() == -3);
+    assert(7i32.checked_div(-2).unwrap() == -3);
+    assert((-2147483648i32).checked_div(-1).is_none());
+    assert(250u32.saturating_sub(300) == 0);
+    assert(4294967295u32.wrapping_add(1) == 0);
+    assert((-7i32).rem_euclid(3) == 2);
+    assert((-7i32).rem_euclid(-3) == 2);
+    assert(3i32.pow(4) == 81);
+    let (a, b) = (3i32, 10i32);
+    assert(a.max(b) == 10 && a.min(b) == 3);
+}
+
+
+
+/*
+
+== Options
+
+Next, lets collect the extern specs for `std::option::Option` from
+@ch:07_externs. First, the _type definition_, which adds a `bool` index
+that tracks whether the value is `Some` ...
+
+*/
+
+
+
+#[extern_spec]
+#[refined_by(valid: bool)]
+enum Option<T> {
+    #[variant(Option<T>[{valid: false}])]
+    None,
+    #[variant((T) -> Option<T>[{valid: true}])]
+    Some(T),
+}
+
+
+
+/*
+
+... and then the _methods_, which relate the `valid` index of the output
+to those of the inputs, exactly as for the refined `Option` in
+@ch:04_enums:combinators.
+
+*/
+
+
+
+#[extern_spec]
+impl<T> Option<T> {
+    #[spec(fn(&Option<T>[@b]) -> bool[b])]
+    const fn is_some(&self) -> bool;
+
+    #[spec(fn(&Option<T>[@b]) -> bool[!b])]
+    const fn is_none(&self) -> bool;
+
+    #[spec(fn(Option<T>[true]) -> T)]
+    const fn unwrap(self) -> T;
+
+    #[spec(fn(Option<T>[@b], f: F) -> Option<U>[b])]
+    fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Option<U>;
+
+    #[spec(fn(Option<T>[@b], f: F) -> Option<U>{v: v => b})]
+    fn and_then<U, F: FnOnce(T) -> Option<U>>(self, f: F) -> Option<U>;
+
+    #[spec(fn(Option<T>[@b1], Option<T>[@b2]) -> Option<T>[b1 || b2])]
+    fn or(self, optb: Option<T>) -> Option<T>;
+
+    #[spec(fn(Option<T>, default: T) -> T)]
+    fn unwrap_or(self, default: T) -> T;
+
+    #[spec(fn(Option<T>[@b], predicate: P) -> Option<T>{v: v => b})]
+    fn filter<P: FnOnce(&T) -> bool>(self, predicate: P) -> Option<T>;
+
+    #[spec(fn(Option<T>[@b1], Option<U>[@b2]) -> Option<(T, U)>[b1 && b2])]
+    fn zip<U>(self, other: Option<U>) -> Option<(T, U)>;
+
+    #[spec(fn(self: &mut Option<T>[@b]) -> Option<T>[b] ensures self: Option<T>[false])]
+    const fn take(&mut self) -> Option<T>;
+
+    #[spec(fn(self: &mut Option<T>[@b], value: T) -> Option<T>[b] ensures self: Option<T>[true])]
+    const fn replace(&mut self, value: T) -> Option<T>;
+}
+
+
+
+/*
+
+Now chains of operations on standard `Option`s keep track of validity.
+
+*/
+
+
+
+fn test_opt_combinators() {
+    let a = Some(1).map(|x| x + 1).unwrap();
+    let b = None.or(Some(2));
+    assert(b.is_some());
+    let (c, d) = Some(3).zip(Some(true)).unwrap();
+    assert(Some(4).zip(None::<bool>).is_none());
+
+    let mut o = Some(5);
+    let e = o.take().unwrap();
+    assert(o.is_none());
+    let f = o.replace(6);
+    assert(f.is_none());
+    let g = o.unwrap();
+}
+
+
+
+/*
+
+== Results
+
+In @ch:04_enums:refined-result we defined our own `Result`, indexed by a
+`bool` that tracks whether it is `Ok`. Lets retrofit the same index onto
+`std::result::Result`, so that functions returning the standard `Result`,
+e.g. to use `?`, can also say exactly when they fail. As with `Option`,
+we need an extern spec for the _type definition_ ...
+
+*/
+
+
+
+#[extern_spec]
+#[refined_by(ok: bool)]
+enum Result<T, E> {
+    #[variant((T) -> Result<T, E>[{ok: true}])]
+    Ok(T),
+    #[variant((E) -> Result<T, E>[{ok: false}])]
+    Err(E),
+}
+
+
+
+/*
+
+... and for the _methods_ that query, or unwrap, a `Result`. The
+`unwrap`s require that the value be in the right variant, so they
+can no longer panic. The combinators preserve the index.
+
+*/
+
+
+
+#[extern_spec]
+impl<T, E> Result<T, E> {
+    #[spec(fn(&Result<T, E>[@b]) -> bool[b])]
+    const fn is_ok(&self) -> bool;
+
+    #[spec(fn(&Result<T, E>[@b]) -> bool[!b])]
+    const fn is_err(&self) -> bool;
+
+    #[spec(fn(Result<T, E>[true]) -> T)]
+    fn unwrap(self) -> T
+    where
+        E: std::fmt::Debug;
+
+    #[spec(fn(Result<T, E>[false]) -> E)]
+    fn unwrap_err(self) -> E
+    where
+        T: std::fmt::Debug;
+
+    #[spec(fn(Result<T, E>[@b], f: F) -> Result<U, E>[b])]
+    fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Result<U, E>;
+
+    #[spec(fn(Result<T, E>[@b], f: O) -> Result<T, F>[b])]
+    fn map_err<F, O: FnOnce(E) -> F>(self, f: O) -> Result<T, F>;
+
+    #[spec(fn(Result<T, E>[@b]) -> Option<T>[b])]
+    fn ok(self) -> Option<T>;
+}
+
+
+
+/*
+
+For example, Flux now knows which of the results below are `Ok`, and
+so that none of the `unwrap`s can panic.
+
+*/
+
+
+
+#[spec(fn(n: i32) -> Result<i32, &'static str>[0 <= n])]
+fn non_negative(n: i32) -> Result<i32, &'static str> {
+    if 0 <= n { Ok(n) } else { Err("negative") }
+}
+
+fn test_result_specs() -> Result<i32, &'static str> {
+    assert(non_negative(5).is_ok());
+    assert(non_negative(-5).is_err());
+    let e = non_negative(-5).unwrap_err();
+    let n = non_negative(5).map(|n| n + 1).unwrap();
+    assert(non_negative(-5).ok().is_none());
+    non_negative(n)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::props::*;
+    use rand::Rng;
+
+    /// The Rust `checked_div` agrees with `trunc_div`, transcribed here on
+    /// `i64`, and `rem_euclid` returns the (Euclidean) `%` of the logic.
+    #[test]
+    fn prop_div_rounding() {
+        let abs = |x: i64| x.abs();
+        let trunc_div = |a: i64, b: i64| {
+            if (0 <= a && 0 < b) || (a < 0 && b < 0) { abs(a) / abs(b) } else { -(abs(a) / abs(b)) }
+        };
+        for_all(
+            "div_rounding",
+            |rng| (rng.gen_range(-1000..1000), [-1, 1][rng.gen_range(0..2)] * rng.gen_range(1..20)),
+            |p| shrink_pair(p, shrink_i64, shrink_i64),
+            |&(_, b)| b != 0,
+            |&(a, b)| {
+                let (x, y) = (a as i32, b as i32);
+                let r = x.rem_euclid(y) as i64;
+                x.checked_div(y) == Some(trunc_div(a, b) as i32)
+                    && 0 <= r
+                    && r < abs(b)
+                    && (a - r) % b == 0
+            },
+        );
+    }
+}
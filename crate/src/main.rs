@@ -9,12 +9,24 @@ flux_rs::defs! {
 
 extern crate flux_alloc;
 extern crate flux_core;
+#[macro_use]
+extern crate spec_contracts;
+
+mod contracts;
 
 pub mod ch01_refinements;
 pub mod ch02_ownership;
 pub mod ch03_structs;
 pub mod ch04_enums;
 pub mod ch05_vectors;
+pub mod ch06_consts;
+pub mod ch08_traits;
+pub mod ch10_scope;
+pub mod externs;
+pub mod math;
+pub mod mem;
+pub mod numeric;
+pub mod props;
 
 fn main() {
     return;
@@ -0,0 +1,504 @@
+/*
+#import "../orly-modified.typ": alert
+
+= A Verified Math Library <ch:math>
+
+*/
+
+
+
+#![allow(unused)]
+extern crate flux_rs;
+use flux_rs::attrs::*;
+use flux_rs::assert;
+
+
+
+/*
+
+In @ch:01_refinements we wrote a few small functions like `abs` and `factorial`
+and gave them specifications that describe their outputs. Lets put the same
+ideas to work to build a small library of integer functions, where _each_
+function comes with
+
+1. a *refinement-level* function, defined with `defs!`, that describes
+   _what_ the function computes, and
+2. a *spec* that says the Rust function computes exactly that.
+
+The refinement-level functions are handy because callers can use them to
+describe the results of the library functions in their _own_ specs.
+
+#alert("info", [
+*Recursive Definitions:* Several of the refinement-level functions below
+are _recursive_. Flux lets us define such functions, and will _unfold_ the
+definitions as needed when checking code. To keep things simple, the Rust
+implementations closely follow the structure of the definitions, which
+makes it easy for Flux to check that the two agree. Where a definition
+recurses more than a handful of times, the Rust code uses a loop instead,
+so that it cannot overflow the stack, and we give Flux a _qualifier_ that
+describes the loop invariant.
+])
+
+== Greatest Common Divisor
+
+Euclid's algorithm computes the greatest common divisor by repeatedly
+replacing the pair `a, b` with `b, a % b` until `b` is zero. Besides
+computing `gcd(a, b)`, the spec says that the result is _positive_ when
+either input is, which Flux checks by following the recursion, and which
+we will need to divide by the result below.
+
+*/
+
+
+
+defs! {
+    fn gcd(a: int, b: int) -> int {
+        if b == 0 { a } else { gcd(b, a % b) }
+    }
+}
+
+#[spec(fn(a: u32, b: u32) -> u32{v: v == gcd(a, b) && (0 < a || 0 < b => 0 < v)})]
+pub fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+
+
+/*
+
+== Least Common Multiple
+
+The least common multiple of two positive numbers is their product divided
+by their `gcd`, which the spec of `gcd` tells us is not `0`; we divide
+_before_ we multiply to keep the numbers small.
+Even so, the result itself may not fit in a `u32`, e.g. for `65536` and `65537`,
+so we _require_ that it does.
+
+*/
+
+
+
+defs! {
+    fn lcm(a: int, b: int) -> int {
+        a / gcd(a, b) * b
+    }
+}
+
+#[spec(fn(a: u32{0 < a}, b: u32{0 < b}) -> u32[lcm(a, b)]
+       requires lcm(a, b) <= 4294967295)]
+pub fn lcm(a: u32, b: u32) -> u32 {
+    a / gcd(a, b) * b
+}
+
+
+
+/*
+
+#alert("success", [
+*EXERCISE:* Why does `lcm` require that `a` and `b` be _positive_?
+What goes wrong when `a` and `b` are both `0`?
+])
+
+== Integer Square Root
+
+The integer square root of `n` is the largest `r` such that `r * r <= n`.
+Instead of computing a value, the refinement-level function `is_isqrt`
+_characterizes_ the result.
+
+*/
+
+
+
+defs! {
+    fn is_isqrt(n: int, r: int) -> bool {
+        r * r <= n && n < (r + 1) * (r + 1)
+    }
+}
+
+
+
+/*
+
+We compute the square root by _binary search_. The helper `isqrt_between`
+_requires_ that the root is in the range `lo..hi`, i.e. `lo * lo <= n` and
+`n < hi * hi`, which is exactly the fact Flux needs to check that the result
+satisfies `is_isqrt` once the range has shrunk to a single number. As every
+`u64` is less than `2^64`, the root is initially in the range `0..2^32`,
+and so there are at most `32` recursive calls.
+//
+We cannot compare `mid * mid <= n` directly, as the product may overflow,
+so instead we compare `mid <= n / mid`, which is equivalent as `0 < mid`.
+
+*/
+
+
+
+#[spec(fn(n: u64, lo: u64, hi: u64) -> u64{v: is_isqrt(n, v)}
+       requires lo < hi && lo * lo <= n && n < hi * hi)]
+fn isqrt_between(n: u64, lo: u64, hi: u64) -> u64 {
+    if hi - lo == 1 {
+        return lo;
+    }
+    let mid = lo + (hi - lo) / 2;
+    if mid <= n / mid {
+        isqrt_between(n, mid, hi)
+    } else {
+        isqrt_between(n, lo, mid)
+    }
+}
+
+#[spec(fn(n: u64) -> u64{v: is_isqrt(n, v)})]
+pub fn isqrt(n: u64) -> u64 {
+    isqrt_between(n, 0, 4294967296)
+}
+
+
+
+/*
+
+== Integer Powers
+
+The power `b` to the `e` is `1` when `e` is `0`, and is otherwise `b`
+times the power `b` to the `e - 1`.
+
+*/
+
+
+
+defs! {
+    fn pow(b: int, e: int) -> int {
+        if e <= 0 { 1 } else { b * pow(b, e - 1) }
+    }
+
+    qualifier PowSoFar(r: int, b: int, i: int) { r == pow(b, i) }
+}
+
+
+
+/*
+
+Rather than recursing `e` times, `ipow` multiplies in a loop, where the
+qualifier `PowSoFar` lets Flux infer the loop invariant `res == pow(b, i)`.
+The power quickly outgrows a `u64`, e.g. `ipow(10, 20)` does not fit,
+so we _require_ that it fits. Then every intermediate `res` fits too, as
+`pow(b, i)` is at most `pow(b, e)` for `0 < i <= e`. However, that takes
+_induction_ to prove, which Flux does not do on its own, so we state it as
+a _lemma_: a function whose spec is the fact, and which `ipow` calls to
+tell Flux the fact just before each multiplication. As with `sorted` in
+@ch:05_vectors, the lemma is `#[trusted]`, and only checks the fact at run
+time, in debug builds.
+
+*/
+
+
+
+#[trusted]
+#[spec(fn(b: u64, i: u32, e: u32) requires 0 < i && i <= e ensures pow(b, i) <= pow(b, e))]
+fn pow_monotone(b: u64, i: u32, e: u32) {
+    debug_assert!(b.saturating_pow(i) <= b.saturating_pow(e));
+}
+
+#[spec(fn(b: u64, e: u32) -> u64[pow(b, e)] requires pow(b, e) <= 18446744073709551615)]
+pub fn ipow(b: u64, e: u32) -> u64 {
+    let mut res = 1;
+    let mut i = 0;
+    while i < e {
+        pow_monotone(b, i + 1, e);
+        res *= b;
+        i += 1;
+    }
+    res
+}
+
+
+
+/*
+
+== Modular Exponentiation
+
+Raising `b` to the `e` quickly overflows, so when we only want the result
+modulo `m`, we reduce modulo `m` at each step. Requiring that `b < m` and
+that `m` is at most `2^32` ensures the intermediate products fit in a `u64`.
+//
+We use the classic _square-and-multiply_ algorithm, which halves `e` at each
+step: `b` to the `e` is `b * b` to the `e / 2`, times one more `b` when `e` is
+odd. So `modular_pow` makes at most `32` recursive calls. The refinement-level
+`mod_pow`, however, is simply `pow(b, e) % m`, which is what we _mean_ by
+modular exponentiation.
+//
+To connect the two, Flux needs to know that reducing modulo `m` at each step
+does not change the final remainder, which again takes induction, as well as
+facts about `%` by the non-constant `m`. So once more we state the one step
+that `modular_pow` takes as a `#[trusted]` lemma, `pow_mod_halves`, which
+`modular_pow` calls before it combines the result of the recursive call.
+
+*/
+
+
+
+defs! {
+    fn mod_pow(b: int, e: int, m: int) -> int {
+        pow(b, e) % m
+    }
+}
+
+#[trusted]
+#[spec(fn(b: u64, e: u32, m: u64)
+       requires 0 < m && m <= 4294967296 && b < m
+       ensures if e % 2 == 0 {
+                   mod_pow(b, e, m) == mod_pow((b * b) % m, e / 2, m)
+               } else {
+                   mod_pow(b, e, m) == (b * mod_pow((b * b) % m, e / 2, m)) % m
+               })]
+fn pow_mod_halves(b: u64, e: u32, m: u64) {
+    let naive = |b: u64, e: u32| (0..e).fold(1 % m, |r, _| (r * b) % m);
+    debug_assert_eq!(
+        naive(b, e),
+        if e.is_multiple_of(2) { naive((b * b) % m, e / 2) } else { (b * naive((b * b) % m, e / 2)) % m }
+    );
+}
+
+#[spec(fn(b: u64, e: u32, m: u64) -> u64[mod_pow(b, e, m)]
+       requires 0 < m && m <= 4294967296 && b < m)]
+pub fn modular_pow(b: u64, e: u32, m: u64) -> u64 {
+    if e == 0 {
+        return 1 % m;
+    }
+    let half = modular_pow((b * b) % m, e / 2, m);
+    pow_mod_halves(b, e, m);
+    if e.is_multiple_of(2) {
+        half
+    } else {
+        (b * half) % m
+    }
+}
+
+
+
+/*
+
+#alert("success", [
+*EXERCISE:* The lemma `pow_mod_halves` is `#[trusted]`. Write down why it
+holds, using `pow(b, e)` is `pow(b * b, e / 2)`, times one more `b` when `e`
+is odd, and that `(x * y) % m` is `((x % m) * (y % m)) % m`.
+])
+
+== Primality
+
+A number `n` is prime if it is at least `2` and has no divisors `d`
+with `2 <= d < n`. It suffices to look for divisors `d` with `d * d <= n`,
+as if `n` is `d * e` then one of `d` and `e` is at most the square root of `n`.
+The refinement-level `no_divisor_from(n, d)` says that none of
+`d, d + 1, ...` up to the square root of `n` divide `n`, where,
+as in `isqrt`, we test `n / d < d` rather than `n < d * d`.
+
+*/
+
+
+
+defs! {
+    fn no_divisor_from(n: int, d: int) -> bool {
+        if n / d < d { true } else { n % d != 0 && no_divisor_from(n, d + 1) }
+    }
+
+    fn is_prime(n: int) -> bool {
+        2 <= n && no_divisor_from(n, 2)
+    }
+
+    qualifier NoDivisorSoFar(n: int, d: int) {
+        no_divisor_from(n, 2) <=> no_divisor_from(n, d)
+    }
+}
+
+
+
+/*
+
+The loop in `is_prime` tries each candidate `d` in turn, and the qualifier
+`NoDivisorSoFar` lets Flux infer the invariant that `n` has no divisor below `d`
+exactly when it has none from `2` on. Even for the largest `u32`, the loop
+takes at most `65536` steps.
+
+*/
+
+
+
+#[spec(fn(n: u32) -> bool[is_prime(n)])]
+pub fn is_prime(n: u32) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let mut d = 2;
+    while d <= n / d {
+        if n.is_multiple_of(d) {
+            return false;
+        }
+        d += 1;
+    }
+    true
+}
+
+
+
+/*
+
+== Rounding Up Division
+
+Dividing `a` by `b` rounds _down_; `div_ceil` rounds _up_ instead.
+We avoid the usual `(a + b - 1) / b` trick as `a + b - 1` may overflow.
+
+*/
+
+
+
+defs! {
+    fn div_ceil(a: int, b: int) -> int {
+        if a % b == 0 { a / b } else { a / b + 1 }
+    }
+}
+
+#[spec(fn(a: u32, b: u32{0 < b}) -> u32[div_ceil(a, b)])]
+pub fn div_ceil(a: u32, b: u32) -> u32 {
+    if a.is_multiple_of(b) { a / b } else { a / b + 1 }
+}
+
+
+
+/*
+
+== Clamping
+
+Finally, `clamp` forces `x` into the range `lo..=hi`, which must be non-empty.
+
+*/
+
+
+
+defs! {
+    fn clamp(x: int, lo: int, hi: int) -> int {
+        if x < lo { lo } else if hi < x { hi } else { x }
+    }
+}
+
+#[spec(fn(x: i32, lo: i32, hi: i32{lo <= hi}) -> i32[clamp(x, lo, hi)])]
+pub fn clamp(x: i32, lo: i32, hi: i32) -> i32 {
+    if x < lo {
+        lo
+    } else if hi < x {
+        hi
+    } else {
+        x
+    }
+}
+
+
+
+/*
+
+== Using the Library
+
+Because the specs are stated in terms of the refinement-level functions,
+callers can use those functions to describe their own results. For example,
+here is a function that splits `total` cents into `parts` installments,
+rounding up so that the installments cover the total.
+
+*/
+
+
+
+#[spec(fn(total: u32, parts: u32{0 < parts}) -> u32[div_ceil(total, parts)])]
+pub fn installment(total: u32, parts: u32) -> u32 {
+    div_ceil(total, parts)
+}
+
+
+
+/*
+
+Flux can also evaluate the definitions on concrete inputs, and so check
+the following assertions at compile time.
+
+*/
+
+
+
+fn test_math() {
+    assert(gcd(12, 18) == 6);
+    assert(lcm(4, 6) == 12);
+    assert(isqrt(17) == 4);
+    assert(ipow(2, 10) == 1024);
+    assert(modular_pow(3, 4, 5) == 1);
+    assert(modular_pow(2, 10, 1000) == 24);
+    assert(is_prime(7));
+    assert(!is_prime(9));
+    assert(div_ceil(7, 2) == 4);
+    assert(clamp(15, 0, 10) == 10);
+}
+
+
+
+/*
+
+== Summary
+
+In this chapter, we saw how to pair each Rust function with a refinement-level
+function that describes what it computes. The specs then say that each Rust
+function computes _exactly_ its refinement-level counterpart, which lets
+callers state and check precise properties of their own code.
+*/
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::props::*;
+    use rand::Rng;
+
+    #[test]
+    fn large_inputs() {
+        assert_eq!(isqrt(1 << 40), 1 << 20);
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+        assert_eq!(ipow(10, 19), 10_000_000_000_000_000_000);
+        assert_eq!(modular_pow(2, 1_000_000, 7), 2);
+        assert!(is_prime(1_000_003));
+        assert!(is_prime(4_294_967_291));
+        assert_eq!(lcm(65536, 65535), 4_294_901_760);
+    }
+
+    #[test]
+    fn prop_isqrt() {
+        for_all(
+            "isqrt",
+            |rng| rng.gen_range(0..=u64::MAX),
+            |&n| if n == 0 { vec![] } else { vec![n / 2] },
+            |_| true,
+            |&n| {
+                let r = isqrt(n) as u128;
+                r * r <= n as u128 && (n as u128) < (r + 1) * (r + 1)
+            },
+        );
+    }
+
+    #[test]
+    fn prop_modular_pow() {
+        for_all(
+            "modular_pow",
+            |rng| (rng.gen_range(0..1000), rng.gen_range(1..1000)),
+            |p| shrink_pair(p, shrink_i64, shrink_i64),
+            |&(e, m)| 0 <= e && 0 < m,
+            |&(e, m)| {
+                let (b, e, m) = (7 % m as u64, e as u32, m as u64);
+                modular_pow(b, e, m) == (0..e).fold(1 % m, |r, _| (r * b) % m)
+            },
+        );
+    }
+
+    #[test]
+    fn prop_is_prime() {
+        for_all("is_prime", |rng| rng.gen_range(0..5000), shrink_i64, |&n| 0 <= n, |&n| {
+            let n = n as u32;
+            is_prime(n) == (2 <= n && (2..n).all(|d| !n.is_multiple_of(d)))
+        });
+    }
+}
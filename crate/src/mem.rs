@@ -0,0 +1,184 @@
+/*
+#import "../orly-modified.typ": alert
+
+= Strong Updates <ch:mem>
+
+*/
+
+
+
+#![allow(unused)]
+extern crate flux_rs;
+use flux_rs::{attrs::*, assert, extern_spec};
+
+
+
+/*
+
+In @ch:02_ownership:strongly-mutable-references we saw how the `ensures`
+clause of `incr` says _exactly_ what value the `&mut i32` refers to when
+`incr` returns. Lets use the same idea to build a small library of helpers
+that move values between mutable references. These are the basic moves of
+code that updates _state_, e.g. the state machines in @ch:04_enums, and the
+`ensures` clauses let Flux track precisely which value ends up where.
+
+== Specs for `std::mem`
+
+The helpers are built on top of `swap`, `replace` and `take` from `std::mem`,
+so first, as in @ch:07_externs, we tell Flux how those functions behave.
+
+*/
+
+
+
+#[extern_spec(std::mem)]
+#[spec(fn(x: &mut T[@vx], y: &mut T[@vy]) ensures x: T[vy], y: T[vx])]
+fn swap<T>(a: &mut T, b: &mut T);
+
+#[extern_spec(std::mem)]
+#[spec(fn(dest: &mut T[@old], src: T[@new]) -> T[old] ensures dest: T[new])]
+fn replace<T>(dest: &mut T, src: T) -> T;
+
+#[extern_spec(std::mem)]
+#[spec(fn(dest: &mut T[@old]) -> T[old] ensures dest: T)]
+fn take<T: Default>(dest: &mut T) -> T;
+
+
+
+/*
+
+The specs say that
+
+- `swap` exchanges the values that `x` and `y` refer to,
+- `replace` returns the _old_ value of `dest`, which then holds `src`, and
+- `take` returns the _old_ value of `dest`, which then holds the `default`.
+
+#alert("info", [
+*What is the Default?* Flux does not know which value `T::default()`
+returns, and so the `ensures` clause for `take` can only say that `dest`
+holds _some_ `T`. We will see how to do better for `i32` below.
+])
+
+== Swapping and Replacing
+
+Our first helpers simply wrap the functions from `std::mem`, but now Flux
+_checks_ that they meet their specs, which are identical to the above.
+
+*/
+
+
+
+#[spec(fn(x: &mut T[@vx], y: &mut T[@vy]) ensures x: T[vy], y: T[vx])]
+pub fn swap<T>(x: &mut T, y: &mut T) {
+    std::mem::swap(x, y)
+}
+
+#[spec(fn(dest: &mut T[@old], src: T[@new]) -> T[old] ensures dest: T[new])]
+pub fn replace<T>(dest: &mut T, src: T) -> T {
+    std::mem::replace(dest, src)
+}
+
+#[spec(fn(dest: &mut T[@old]) -> T[old] ensures dest: T)]
+pub fn take<T: Default>(dest: &mut T) -> T {
+    std::mem::take(dest)
+}
+
+
+
+/*
+
+For integers, we _do_ know the default value, so `take_i32` can say that
+`dest` holds `0` afterwards. We use `replace` to put the `0` in place.
+
+*/
+
+
+
+#[spec(fn(dest: &mut i32[@old]) -> i32[old] ensures dest: i32[0])]
+pub fn take_i32(dest: &mut i32) -> i32 {
+    replace(dest, 0)
+}
+
+
+
+/*
+
+== Rotating
+
+We can combine the helpers to build bigger moves. For example, `rotate3`
+rotates three values to the left, using _two_ swaps. Flux tracks the values
+through each `swap` to check the final `ensures` clause.
+
+*/
+
+
+
+#[spec(fn(a: &mut T[@va], b: &mut T[@vb], c: &mut T[@vc])
+       ensures a: T[vb], b: T[vc], c: T[va])]
+pub fn rotate3<T>(a: &mut T, b: &mut T, c: &mut T) {
+    swap(a, b); // a: T[vb], b: T[va]
+    swap(b, c); // b: T[vc], c: T[va]
+}
+
+
+
+/*
+
+#alert("success", [
+*EXERCISE:* Write `rotate3_right`, which rotates the values to the _right_,
+i.e. afterwards `a`, `b` and `c` hold the old values of `c`, `a` and `b`.
+Can you implement it using `rotate3`?
+])
+
+== Sorting Two Values
+
+Finally, `min_max_sort2` puts two integers in order, so that `x` holds the
+smaller and `y` the larger value, as described with the refinement functions
+`min` and `max` from @ch:03_structs.
+
+*/
+
+
+
+#[spec(fn(x: &mut i32[@a], y: &mut i32[@b]) ensures x: i32[min(a, b)], y: i32[max(a, b)])]
+pub fn min_max_sort2(x: &mut i32, y: &mut i32) {
+    if *y < *x {
+        swap(x, y);
+    }
+}
+
+
+
+/*
+
+Here is a little test that Flux checks using only the `ensures` clauses.
+
+*/
+
+
+
+fn test_mem() {
+    let mut x = 1;
+    let mut y = 2;
+    let mut z = 3;
+    rotate3(&mut x, &mut y, &mut z);
+    assert(x == 2 && y == 3 && z == 1);
+    min_max_sort2(&mut y, &mut z);
+    assert(y == 1 && z == 3);
+    let old = replace(&mut x, 10);
+    assert(old == 2 && x == 10);
+    let old = take_i32(&mut x);
+    assert(old == 10 && x == 0);
+}
+
+
+
+/*
+
+== Summary
+
+In this chapter, we saw how the `ensures` clauses of strongly mutable
+references describe _exactly_ which value each location holds after a call.
+By specifying the basic moves of `std::mem` once, we can build (and verify)
+bigger moves like `rotate3` and `min_max_sort2` on top of them.
+*/
@@ -0,0 +1,583 @@
+/*
+#import "../orly-modified.typ": alert
+
+= Refined Numeric Types <ch:numeric>
+
+*/
+
+
+
+#![allow(unused)]
+extern crate flux_rs;
+use flux_rs::attrs::*;
+use flux_rs::assert;
+use std::ops::{Add, Div, Mul, Neg, Rem};
+
+
+
+/*
+
+In @ch:03_structs:positive-integers we wrote a `Positivei32` by hand, which
+@ch:04_enums then reused. Code that needs such types for _other_ widths, or
+other invariants, should not have to write them over and over. So this
+module defines a _family_ of refined numeric types, once, for every integer
+width:
+
+- `NonNegative` integers `n` with `0 <= n`,
+- `Positive` integers `n` with `0 < n`, and
+- `NonZero` integers `n` with `n != 0`,
+
+together with `Percentage`s and (fixed-point) `Probability`s. Each type
+comes with
+
+1. a smart constructor `new` that returns a valid `Option` _exactly_ when
+   its input satisfies the invariant,
+2. a method `get` and an `Into` conversion back to the underlying integer, and
+3. the arithmetic operators that _preserve_ the invariant.
+
+The sum of two `Positive` numbers is only positive if it does not
+_overflow_, so, as for the `Positivei32` of
+@ch:03_structs:positive-arithmetic, each operator overrides the
+associated refinement `allowed` from @ch:08_traits:operators to
+_require_ that its result fits in the type.
+
+== Non-Negative Integers
+
+We use `macro_rules!` to generate the same definitions for each width. The
+macro `non_negative!` takes the integer type `$t`, the name `$Name` of the
+new type, and the smallest and largest values `$lo` and `$hi` of `$t`.
+Every _unsigned_ integer is non-negative, so for those, `new` cannot fail:
+it still returns an `Option`, like every other constructor in the family,
+but one whose `spec` says that it is _always_ valid.
+
+*/
+
+
+
+macro_rules! non_negative {
+    (signed $t:ident, $Name:ident, $lo:literal, $hi:literal) => {
+        non_negative!(@common $t, $Name, $lo, $hi);
+
+        impl $Name {
+            #[spec(fn(x: $t) -> Option<$Name[x]>[0 <= x])]
+            pub fn new(x: $t) -> Option<$Name> {
+                if 0 <= x { Some($Name { val: x }) } else { None }
+            }
+        }
+    };
+    (unsigned $t:ident, $Name:ident, $lo:literal, $hi:literal) => {
+        non_negative!(@common $t, $Name, $lo, $hi);
+
+        impl $Name {
+            #[spec(fn(x: $t) -> Option<$Name[x]>[true])]
+            pub fn new(x: $t) -> Option<$Name> {
+                Some($Name { val: x })
+            }
+        }
+    };
+    (@common $t:ident, $Name:ident, $lo:literal, $hi:literal) => {
+        #[refined_by(n: int)]
+        #[invariant(0 <= n)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct $Name {
+            #[field($t[n])]
+            val: $t,
+        }
+
+        impl $Name {
+            #[spec(fn(&$Name[@n]) -> $t[n])]
+            pub fn get(&self) -> $t {
+                self.val
+            }
+        }
+
+        impl From<$Name> for $t {
+            #[spec(fn($Name[@n]) -> $t[n])]
+            fn from(x: $Name) -> $t {
+                x.val
+            }
+        }
+
+        #[assoc(fn allowed(a: int, b: int) -> bool { a + b <= $hi })]
+        impl Add for $Name {
+            type Output = $Name;
+
+            #[spec(fn(Self[@a], b: Self{Self::allowed(a, b)}) -> Self[a + b])]
+            fn add(self, other: $Name) -> $Name {
+                $Name { val: self.val + other.val }
+            }
+        }
+
+        #[assoc(fn allowed(a: int, b: int) -> bool { a * b <= $hi })]
+        impl Mul for $Name {
+            type Output = $Name;
+
+            #[spec(fn(Self[@a], b: Self{Self::allowed(a, b)}) -> Self[a * b])]
+            fn mul(self, other: $Name) -> $Name {
+                $Name { val: self.val * other.val }
+            }
+        }
+    };
+}
+
+
+
+/*
+
+The `spec`s for the operators say that they compute the _exact_ sum and
+product, and Flux checks that these are non-negative, as required by the
+`invariant`. As both operands are non-negative, the result can only overflow
+_above_, so `allowed` says that it is at most `$hi`. Of course, we cannot
+subtract: `1 - 2` is not non-negative!
+
+== Positive Integers
+
+`Positive` numbers additionally let us _divide_ (and take the remainder)
+without fear: the divisor is never zero, so `x / d` never panics. Rust's `/`
+_truncates_, unlike the `/` of the logic, so the `spec`s use `trunc_div`
+from @ch:externs, and describe the remainder as what is left over after
+the (truncating) division. For unsigned `$t`, the two divisions agree.
+
+These are the very definitions of the `Positivei32` of @ch:03_structs, so
+rather than generate a second copy, the family simply _reuses_ that one for
+`i32`, and the macro `positive!` is only used for the other widths.
+
+*/
+
+
+
+macro_rules! positive {
+    ($t:ident, $Name:ident, $lo:literal, $hi:literal) => {
+        #[refined_by(n: int)]
+        #[invariant(0 < n)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct $Name {
+            #[field($t[n])]
+            val: $t,
+        }
+
+        impl $Name {
+            #[spec(fn(x: $t) -> Option<$Name[x]>[0 < x])]
+            pub fn new(x: $t) -> Option<$Name> {
+                if 0 < x { Some($Name { val: x }) } else { None }
+            }
+
+            #[spec(fn(&$Name[@n]) -> $t[n])]
+            pub fn get(&self) -> $t {
+                self.val
+            }
+        }
+
+        impl From<$Name> for $t {
+            #[spec(fn($Name[@n]) -> $t[n])]
+            fn from(x: $Name) -> $t {
+                x.val
+            }
+        }
+
+        #[assoc(fn allowed(a: int, b: int) -> bool { a + b <= $hi })]
+        impl Add for $Name {
+            type Output = $Name;
+
+            #[spec(fn(Self[@a], b: Self{Self::allowed(a, b)}) -> Self[a + b])]
+            fn add(self, other: $Name) -> $Name {
+                $Name { val: self.val + other.val }
+            }
+        }
+
+        #[assoc(fn allowed(a: int, b: int) -> bool { a * b <= $hi })]
+        impl Mul for $Name {
+            type Output = $Name;
+
+            #[spec(fn(Self[@a], b: Self{Self::allowed(a, b)}) -> Self[a * b])]
+            fn mul(self, other: $Name) -> $Name {
+                $Name { val: self.val * other.val }
+            }
+        }
+
+        impl Div<$Name> for $t {
+            type Output = $t;
+
+            #[spec(fn($t[@a], $Name[@d]) -> $t[trunc_div(a, d)])]
+            fn div(self, d: $Name) -> $t {
+                self / d.val
+            }
+        }
+
+        impl Rem<$Name> for $t {
+            type Output = $t;
+
+            #[spec(fn($t[@a], $Name[@d]) -> $t[a - d * trunc_div(a, d)])]
+            fn rem(self, d: $Name) -> $t {
+                self % d.val
+            }
+        }
+    };
+}
+
+
+
+/*
+
+#alert("info", [
+*Dividing Signed Integers:* Dividing by a `NonZero` number is _not_ enough
+to avoid panics, as `i32::MIN / -1` overflows. Dividing by a `Positive`
+number avoids both problems.
+])
+
+== Non-Zero Integers
+
+Non-zero numbers are closed under multiplication and, for signed integers,
+under negation. The product of two non-zero numbers may overflow in either
+direction, and we cannot negate the smallest number `$lo`, as `-$lo` is one
+more than `$hi`.
+
+*/
+
+
+
+macro_rules! non_zero {
+    ($t:ident, $Name:ident, $lo:literal, $hi:literal) => {
+        #[refined_by(n: int)]
+        #[invariant(n != 0)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct $Name {
+            #[field($t[n])]
+            val: $t,
+        }
+
+        impl $Name {
+            #[spec(fn(x: $t) -> Option<$Name[x]>[x != 0])]
+            pub fn new(x: $t) -> Option<$Name> {
+                if x != 0 { Some($Name { val: x }) } else { None }
+            }
+
+            #[spec(fn(&$Name[@n]) -> $t[n])]
+            pub fn get(&self) -> $t {
+                self.val
+            }
+        }
+
+        impl From<$Name> for $t {
+            #[spec(fn($Name[@n]) -> $t[n])]
+            fn from(x: $Name) -> $t {
+                x.val
+            }
+        }
+
+        #[assoc(fn allowed(a: int, b: int) -> bool { $lo <= a * b && a * b <= $hi })]
+        impl Mul for $Name {
+            type Output = $Name;
+
+            #[spec(fn(Self[@a], b: Self{Self::allowed(a, b)}) -> Self[a * b])]
+            fn mul(self, other: $Name) -> $Name {
+                $Name { val: self.val * other.val }
+            }
+        }
+
+        #[assoc(fn allowed(a: int) -> bool { $lo < a })]
+        impl Neg for $Name {
+            type Output = $Name;
+
+            #[spec(fn(a: Self{Self::allowed(a)}) -> Self[-a])]
+            fn neg(self) -> $Name {
+                $Name { val: -self.val }
+            }
+        }
+    };
+}
+
+
+
+/*
+
+== The Family
+
+Every `Positive` number is also `NonNegative` and `NonZero`, so we can
+_weaken_ a `Positive` into either, keeping its value.
+
+*/
+
+
+
+macro_rules! weaken {
+    ($From:ident => $To:ident) => {
+        impl From<$From> for $To {
+            #[spec(fn($From[@n]) -> $To[n])]
+            fn from(x: $From) -> $To {
+                $To { val: x.get() }
+            }
+        }
+    };
+}
+
+macro_rules! signed {
+    ($($t:ident[$lo:literal, $hi:literal]: $NonNegative:ident, $Positive:ident, $NonZero:ident;)*) => {
+        $(
+            non_negative!(signed $t, $NonNegative, $lo, $hi);
+            non_zero!($t, $NonZero, $lo, $hi);
+            weaken!($Positive => $NonNegative);
+            weaken!($Positive => $NonZero);
+        )*
+    };
+}
+
+
+
+/*
+
+For _unsigned_ integers, every number is non-negative, so `NonNegative`
+only serves to document intent (and to get the guarded operators), and
+the non-zero numbers are exactly the positive ones, so we make `NonZero`
+an alias for `Positive`. (As in @ch:externs, we assume that `isize` and
+`usize` are 64 bits wide.)
+
+*/
+
+
+
+macro_rules! unsigned {
+    ($($t:ident[$lo:literal, $hi:literal]: $NonNegative:ident, $Positive:ident, $NonZero:ident;)*) => {
+        $(
+            non_negative!(unsigned $t, $NonNegative, $lo, $hi);
+            positive!($t, $Positive, $lo, $hi);
+            pub type $NonZero = $Positive;
+            weaken!($Positive => $NonNegative);
+        )*
+    };
+}
+
+pub use crate::ch03_structs::Positivei32;
+positive!(i8, Positivei8, -128, 127);
+positive!(i16, Positivei16, -32768, 32767);
+positive!(i64, Positivei64, -9223372036854775808, 9223372036854775807);
+positive!(isize, Positiveisize, -9223372036854775808, 9223372036854775807);
+
+signed! {
+    i8[-128, 127]: NonNegativei8, Positivei8, NonZeroi8;
+    i16[-32768, 32767]: NonNegativei16, Positivei16, NonZeroi16;
+    i32[-2147483648, 2147483647]: NonNegativei32, Positivei32, NonZeroi32;
+    i64[-9223372036854775808, 9223372036854775807]: NonNegativei64, Positivei64, NonZeroi64;
+    isize[-9223372036854775808, 9223372036854775807]: NonNegativeisize, Positiveisize, NonZeroisize;
+}
+
+unsigned! {
+    u8[0, 255]: NonNegativeu8, Positiveu8, NonZerou8;
+    u16[0, 65535]: NonNegativeu16, Positiveu16, NonZerou16;
+    u32[0, 4294967295]: NonNegativeu32, Positiveu32, NonZerou32;
+    u64[0, 18446744073709551615]: NonNegativeu64, Positiveu64, NonZerou64;
+    usize[0, 18446744073709551615]: NonNegativeusize, Positiveusize, NonZerousize;
+}
+
+
+
+/*
+
+== Percentages
+
+A `Percentage` is a `u8` between `0` and `100`. We can take the
+`complement` of a percentage, and compute the percentage `of` a number.
+The obvious `x * p / 100` overflows for large `x`, so instead, `of` splits
+`x` into `x / 100` hundreds and a remainder `x % 100`, and takes the
+percentage of each part. This computes the same number, as the hundreds
+are divided exactly, and neither part, nor their sum, exceeds `x`.
+
+*/
+
+
+
+#[refined_by(p: int)]
+#[invariant(0 <= p && p <= 100)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Percentage {
+    #[field(u8[p])]
+    val: u8,
+}
+
+impl Percentage {
+    #[spec(fn(x: u8) -> Option<Percentage[x]>[x <= 100])]
+    pub fn new(x: u8) -> Option<Percentage> {
+        if x <= 100 { Some(Percentage { val: x }) } else { None }
+    }
+
+    #[spec(fn(&Percentage[@p]) -> u8[p])]
+    pub fn get(&self) -> u8 {
+        self.val
+    }
+
+    #[spec(fn(&Percentage[@p]) -> Percentage[100 - p])]
+    pub fn complement(&self) -> Percentage {
+        Percentage { val: 100 - self.val }
+    }
+
+    #[spec(fn(&Percentage[@p], x: u64) -> u64[x / 100 * p + x % 100 * p / 100])]
+    pub fn of(&self, x: u64) -> u64 {
+        let p = self.val as u64;
+        x / 100 * p + x % 100 * p / 100
+    }
+}
+
+impl From<Percentage> for u8 {
+    #[spec(fn(Percentage[@p]) -> u8[p])]
+    fn from(x: Percentage) -> u8 {
+        x.val
+    }
+}
+
+
+
+/*
+
+== Probabilities
+
+Floating point numbers are not refined by Flux, so we represent a
+`Probability` in _fixed-point_, as a whole number of _basis points_,
+i.e. ten-thousandths, between `0` (impossible) and `10000` (certain).
+The product of two probabilities, i.e. the probability that two independent
+events both happen, is again a probability, and so is the `complement`.
+
+*/
+
+
+
+#[refined_by(p: int)]
+#[invariant(0 <= p && p <= 10000)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Probability {
+    #[field(u32[p])]
+    val: u32,
+}
+
+impl Probability {
+    /// The number of basis points in a certain event.
+    pub const ONE: u32 = 10000;
+
+    #[spec(fn(x: u32) -> Option<Probability[x]>[x <= 10000])]
+    pub fn new(x: u32) -> Option<Probability> {
+        if x <= Self::ONE { Some(Probability { val: x }) } else { None }
+    }
+
+    #[spec(fn() -> Probability[10000])]
+    pub fn certain() -> Probability {
+        Probability { val: 10000 }
+    }
+
+    #[spec(fn() -> Probability[0])]
+    pub fn impossible() -> Probability {
+        Probability { val: 0 }
+    }
+
+    #[spec(fn(&Probability[@p]) -> u32[p])]
+    pub fn get(&self) -> u32 {
+        self.val
+    }
+
+    #[spec(fn(&Probability[@p]) -> Probability[10000 - p])]
+    pub fn complement(&self) -> Probability {
+        Probability { val: 10000 - self.val }
+    }
+}
+
+impl Mul for Probability {
+    type Output = Probability;
+
+    #[spec(fn(Probability[@p], Probability[@q]) -> Probability[p * q / 10000])]
+    fn mul(self, other: Probability) -> Probability {
+        Probability { val: self.val * other.val / 10000 }
+    }
+}
+
+impl From<Percentage> for Probability {
+    #[spec(fn(Percentage[@p]) -> Probability[p * 100])]
+    fn from(x: Percentage) -> Probability {
+        Probability { val: x.val as u32 * 100 }
+    }
+}
+
+impl From<Probability> for u32 {
+    #[spec(fn(Probability[@p]) -> u32[p])]
+    fn from(x: Probability) -> u32 {
+        x.val
+    }
+}
+
+
+
+/*
+
+== Using the Family
+
+The specs let Flux check facts about code that mixes the types.
+
+*/
+
+
+
+fn test_numeric() {
+    let two = Positivei32::new(2).unwrap();          // two: Positivei32[2]
+    let three = Positivei32::new(3).unwrap();        // three: Positivei32[3]
+    assert((two + three).get() == 5);
+    assert(17 / three == 5);                         // can't divide by zero!
+    assert(-17 / three == -5 && -17 % three == -2);  // rounds towards zero
+    let n: NonNegativei32 = (two * three).into();    // n: NonNegativei32[6]
+    assert(n.get() == 6);
+    let k: i32 = (-NonZeroi32::new(4).unwrap()).into();
+    assert(k == -4);
+    let half = Percentage::new(50).unwrap();
+    assert(half.of(30) == 15);
+    let p: Probability = half.into();                // p: Probability[5000]
+    assert((p * p).complement().get() == 7500);
+    assert(Positiveu8::new(0).is_none());
+    let m: NonNegativeu8 = Positiveu8::new(200).unwrap().into();
+    assert((m + NonNegativeu8::new(55).unwrap()).get() == 255);
+    let big = Positivei32::new(2147483647).unwrap();
+    let oops = big + two;                            // rejected!
+}
+
+
+
+/*
+
+== Summary
+
+In this chapter, we used `macro_rules!` to define a family of refined
+numeric types for every integer width. The smart constructors, accessors
+and operators all have precise specs, so code that uses these types gets
+the invariants (and exact values) for free.
+*/
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::props::*;
+    use rand::Rng;
+
+    #[test]
+    fn prop_percentage_of() {
+        for_all(
+            "percentage_of",
+            |rng| (rng.gen_range(0..=100), rng.gen_range(0..=u64::MAX)),
+            |&(p, x): &(u8, u64)| vec![(p / 2, x), (p, x / 2)],
+            |_| true,
+            |&(p, x)| {
+                let exact = x as u128 * p as u128 / 100;
+                Percentage::new(p).unwrap().of(x) as u128 == exact
+            },
+        );
+    }
+    /// Dividing by a `Positive` truncates, as the `spec`s say with `trunc_div`,
+    /// which is transcribed here, for every `i8` and every positive divisor.
+    #[test]
+    fn positive_div_rem_truncate() {
+        let trunc_div = |a: i32, d: i32| if 0 <= a { a / d } else { -(-a / d) };
+        for a in i8::MIN..=i8::MAX {
+            for d in 1..=i8::MAX {
+                let (p, q) = (Positivei8::new(d).unwrap(), trunc_div(a as i32, d as i32));
+                assert_eq!((a / p) as i32, q);
+                assert_eq!((a % p) as i32, a as i32 - d as i32 * q);
+            }
+        }
+    }
+}
@@ -169,11 +169,20 @@ This is a tutorial about how to write Rust with refinement types.
 #include("typ/ch10_iteration.typ")  // TODO
 
 // Part III Case Studies -----------------------------------------------
+#include("typ/ch10_scope.typ")
 #include("typ/ch11_equality.typ")
 #include("typ/ch12_sets.typ")
 #include("typ/ch13_bitvectors.typ")
 #include("typ/ch14_neural.typ")
 
+// Part IV Libraries ---------------------------------------------------
+#include("typ/externs.typ")
+#include("typ/numeric.typ")
+#include("typ/math.typ")
+#include("typ/mem.typ")
+#include("typ/contracts.typ")
+#include("typ/props.typ")
+
 /*
 TODO
 - [ ]  split into parts
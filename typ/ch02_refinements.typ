@@ -207,6 +207,7 @@ value of an `i32` and give it a refined type which
 says the result is non-negative _and_ exceeds the input `n`.
 
 ```flux
+#[contract]
 #[spec(fn (n:i32) -> i32{v: 0 <= v && n <= v})]
 pub fn abs(n: i32) -> i32 {
     if 0 <= n {
@@ -253,6 +254,7 @@ points to a score `s`. We want to specify that
 - The _output_ equals `s + k`.
 
 ```flux
+#[contract]
 #[spec(fn (s:usize{s + k <= 100}, k:usize) -> usize[s + k])]
 fn add_points(s: usize, k: usize) -> usize {
     s + k
@@ -279,6 +281,7 @@ Note that we
 As a last example, lets write a function to compute the factorial of `n`
 
 ```flux
+#[contract]
 #[spec(fn (n:i32) -> i32{v:1<=v && n<=v})]
 pub fn factorial(n: i32) -> i32 {
     let mut i = 0;
@@ -319,3 +322,47 @@ In the next chapter, we will see how Flux melds refinements and Rust's ownership
 
 // [flux-grammar]: https://github.com/flux-rs/flux/blob/main/book/src/guide/specs.md#grammar-of-refinements
 // [flux-github]: https://github.com/liquid-rust/flux/
+```flux
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::props::*;
+    use rand::Rng;
+
+    #[test]
+    fn prop_is_pos() {
+        for_all(
+            "is_pos",
+            |rng| rng.gen_range(-1000..1000),
+            |n: &i64| shrink_i64(n),
+            |_| true,
+            |n: &i64| is_pos(*n as i32) == (0 < *n),
+        );
+    }
+
+    #[test]
+    fn prop_abs() {
+        for_all(
+            "abs",
+            |rng| rng.gen_range(i32::MIN as i64 + 1..=i32::MAX as i64),
+            |n: &i64| shrink_i64(n),
+            |&n: &i64| abs_requires(n as i32),
+            |&n: &i64| abs_ensures(n as i32, abs(n as i32)),
+        );
+    }
+
+    #[test]
+    fn prop_add_points() {
+        for_all(
+            "add_points",
+            |rng| {
+                let s = rng.gen_range(0..=100);
+                (s, rng.gen_range(0..=100 - s))
+            },
+            |p: &(usize, usize)| shrink_pair(p, shrink_usize, shrink_usize),
+            |&(s, k): &(usize, usize)| add_points_requires(s, k),
+            |&(s, k): &(usize, usize)| add_points_ensures(s, k, add_points(s, k)),
+        );
+    }
+}
+```
@@ -6,6 +6,7 @@
 #![allow(unused)]
 extern crate flux_rs;
 use flux_rs::attrs::*;
+use std::ops::{Add, Div, Mul, Rem, Sub};
 
 #[flux_rs::spec(fn (bool[true]))]
 fn assert(b: bool) {
@@ -42,7 +43,8 @@ With Flux, you can define the `Positivei32` type as follows:
 ```flux
 #[refined_by(n: int)]
 #[invariant(n > 0)]
-struct Positivei32 {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Positivei32 {
   #[field(i32[n])]
   val: i32
 }
@@ -102,16 +104,25 @@ indicating that as the index `n` gets the value `0` the invariant `n > 0` does n
 
 === A Constructor
 
-#alert("success", [
-*EXERCISE:* Consider the following `new` constructor for `Positivei32`.
-Why does Flux reject it? Can you figure out how to fix the `spec` for
-the constructor so Flux will be appeased?
-])
+A constructor `new` that simply wraps any `val` would be rejected, for
+the same reason as `mk_positive_0`: Flux cannot prove the `invariant`
+for an arbitrary `i32`. Instead, `new` _checks_ its input. An `Option` is
+refined by a `bool` that says whether it is a `Some` (see
+@ch:05_enums:refined-option), so the `spec` says _exactly_ when `new`
+succeeds, namely when `val` is positive, and that the result then wraps
+_exactly_ `val`. (The `#[contract]` attribute lets us also check the `spec`
+at run time, as we will see in @ch:contracts.)
 
 ```flux
 impl Positivei32 {
-  pub fn new(val: i32) -> Self {
-    Positivei32 { val }
+  #[contract]
+  #[spec(fn(val: i32) -> Option<Self[val]>[0 < val])]
+  pub fn new(val: i32) -> Option<Self> {
+    if 0 < val {
+      Some(Positivei32 { val })
+    } else {
+      None
+    }
   }
 }
 ```
@@ -120,16 +131,51 @@ impl Positivei32 {
 
 === A "Smart" Constructor
 
+A `None` tells us _that_ the input was rejected, but not _why_. When the
+input is, e.g., read from a file or typed in by a user, we want to report
+the problem, so a _smart_ constructor returns a `Result` instead, and when
+`val` is invalid, an `Err` that carries it, so that the caller can report
+_why_ the input was rejected. The error has its own `invariant`: the
+rejected value is never positive.
+
+```flux
+#[refined_by(val: int)]
+#[invariant(val <= 0)]
+#[derive(Debug)]
+pub struct NonPositiveError {
+  #[field(i32[val])]
+  pub val: i32,
+}
+
+impl std::fmt::Display for NonPositiveError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "{} is not positive", self.val)
+  }
+}
+
+impl std::error::Error for NonPositiveError {}
+```
+
+Like `Option`, a `Result` is refined by a `bool` that says whether it is
+an `Ok` (see @ch:05_enums:refined-result). So the `spec` for `try_new` says
+_exactly_ which variant is returned for which input: an `Ok` with a
+`Positivei32[val]` when `val` is positive, and otherwise, an `Err` with a
+`NonPositiveError[val]`.
+
 #alert("success", [
-*EXERCISE:* Here is a different, constructor that should work
-for _any_ input `n` but which may return `None` if the input is
-invalid. Can you _fix the code_ so that Flux accepts `new_opt`?
+*EXERCISE:* What happens if you change the check `0 < val` in `try_new`
+to `0 <= val`? Why does Flux complain, and where?
 ])
 
 ```flux
 impl Positivei32 {
-  pub fn new_opt(val: i32) -> Option<Self> {
-      Some(Positivei32 { val })
+  #[spec(fn(val: i32) -> Result<Positivei32[val], NonPositiveError[val]>[0 < val])]
+  pub fn try_new(val: i32) -> Result<Self, NonPositiveError> {
+    if 0 < val {
+      Ok(Positivei32 { val })
+    } else {
+      Err(NonPositiveError { val })
+    }
   }
 }
 ```
@@ -189,19 +235,15 @@ Flux is tracking about the different variables, as shown below.
 
 === Tracking the Value in the Constructor
 
-#alert("success", [
-*EXERCISE:* Scroll back up, and modify the `spec` for `new`
-so that the below code verifies. That is, modify the `spec`
-so that it says what the value of `val` is when `new` returns
-a `Positivei32`. You will likely need to _combine_ indexes
-and constraints as shown in the example `add_points` in
-@ch:02_refinements:combining-indexes-and-constraints.
-])
+As the `spec` for `new` says what the value of `val` is when `new`
+returns `Some` `Positivei32`, Flux can check the code below. Note how the
+`spec` _combines_ indexes and constraints, as in the example `add_points`
+in @ch:02_refinements:combining-indexes-and-constraints.
 
 ```flux
 #[spec(fn() -> i32[99])]
 fn test_new() -> i32 {
-    let p = Positivei32::new(99);
+    let p = Positivei32::new(99).unwrap();
     let res = p.val;
     res
 }
@@ -224,6 +266,116 @@ _type-level property_ that only lives at compile-time.
 
 // <!-- SLIDE -->
 
+=== Arithmetic on Positive Integers <ch:04_structs:positive-arithmetic>
+
+To write `a + b` for two `Positivei32`s, we implement the `std::ops`
+operator traits. The sum and product of two positive numbers are positive,
+but they may _overflow_, so `Add` and `Mul` override the associated
+refinement `allowed` (which we will see in @ch:09_traits:operators)
+to require that the result fits in an `i32`.
+
+```flux
+#[assoc(fn allowed(a: int, b: int) -> bool { a + b <= 2147483647 })]
+impl Add for Positivei32 {
+  type Output = Positivei32;
+
+  #[spec(fn(Self[@a], b: Self{Self::allowed(a, b)}) -> Self[a + b])]
+  fn add(self, other: Positivei32) -> Positivei32 {
+    Positivei32 { val: self.val + other.val }
+  }
+}
+
+#[assoc(fn allowed(a: int, b: int) -> bool { a * b <= 2147483647 })]
+impl Mul for Positivei32 {
+  type Output = Positivei32;
+
+  #[spec(fn(Self[@a], b: Self{Self::allowed(a, b)}) -> Self[a * b])]
+  fn mul(self, other: Positivei32) -> Positivei32 {
+    Positivei32 { val: self.val * other.val }
+  }
+}
+```
+
+The difference of two positive numbers never overflows, but it is only
+positive when `a > b`, so `Sub` returns a valid `Option` exactly then.
+
+```flux
+impl Sub for Positivei32 {
+  type Output = Option<Positivei32>;
+
+  #[spec(fn(Self[@a], Self[@b]) -> Option<Self[a - b]>[b < a])]
+  fn sub(self, other: Positivei32) -> Option<Positivei32> {
+    if other.val < self.val {
+      Some(Positivei32 { val: self.val - other.val })
+    } else {
+      None
+    }
+  }
+}
+```
+
+Finally, we can divide (and take the remainder of) _any_ `i32` by a
+`Positivei32`. The operations are total: the divisor is never zero, and as
+it is positive, we also avoid the overflow in `i32::MIN / -1`. Careful,
+though: Rust's `/` _truncates_ towards zero, while the `/` of the logic
+rounds down (for positive divisors), e.g. `-7 / 2` is `-3` in Rust but `-4`
+in the logic. So the `spec`s use `trunc_div`, which we will meet in
+@ch:externs, and describe the remainder as what is left over after the
+truncating division.
+
+```flux
+impl Div<Positivei32> for i32 {
+  type Output = i32;
+
+  #[spec(fn(i32[@a], Positivei32[@b]) -> i32[trunc_div(a, b)])]
+  fn div(self, other: Positivei32) -> i32 {
+    self / other.val
+  }
+}
+
+impl Rem<Positivei32> for i32 {
+  type Output = i32;
+
+  #[spec(fn(i32[@a], Positivei32[@b]) -> i32[a - b * trunc_div(a, b)])]
+  fn rem(self, other: Positivei32) -> i32 {
+    self % other.val
+  }
+}
+
+fn test_positive_ops() {
+  let a = Positivei32 { val: 5 } + Positivei32 { val: 3 };   // a: Positivei32[8]
+  assert(a.val == 8);
+  assert((Positivei32 { val: 5 } - Positivei32 { val: 3 }).is_some());
+  assert((Positivei32 { val: 3 } - Positivei32 { val: 5 }).is_none());
+  assert(17 / Positivei32 { val: 5 } == 3);
+  assert(17 % Positivei32 { val: 5 } == 2);
+  assert(-7 / Positivei32 { val: 2 } == -3);
+  assert(-7 % Positivei32 { val: 2 } == -1);
+  let b = Positivei32 { val: 2147483647 } + Positivei32 { val: 1 }; // rejected!
+}
+```
+
+Other modules cannot see the private field `val`, so to read the value back
+out, `Positivei32` also has a method `get` and a conversion `Into<i32>`.
+Together with `new` and the operators, these are the same API that the
+`Positive` types of @ch:numeric have, which reuse this very `Positivei32`.
+
+```flux
+impl Positivei32 {
+  #[spec(fn(&Self[@n]) -> i32[n])]
+  pub fn get(&self) -> i32 {
+    self.val
+  }
+}
+
+impl From<Positivei32> for i32 {
+  #[spec(fn(Positivei32[@n]) -> i32[n])]
+  fn from(x: Positivei32) -> i32 {
+    x.val
+  }
+}
+```
+
 == Integers in a Range
 
 Of course, we can index and constrain `struct`s with multiple fields.
@@ -234,7 +386,7 @@ where `start <= end`.
 ```flux
 #[refined_by(start: int, end: int)]
 #[invariant(start <= end)]
-struct Range {
+pub struct Range {
   #[field(i32[start])]
   start: i32,
   #[field(i32[end])]
@@ -282,18 +434,15 @@ note: this is the condition that cannot be proved
 
 === A Range Constructor
 
-#alert("success", [
-*EXERCISE:* Fix the specification of the `new`
-constructor for `Range` so that both `new` and
-`test_range_new` are accepted by Flux.
-//
-You will need to _combine_ indexes and constraints
-as shown in the example `add_points` shown
-in @ch:02_refinements:combining-indexes-and-constraints.
-])
+As for `Positivei32`, the constructor `new` _requires_ an input that
+satisfies the `invariant`, i.e. that `start <= end`, and returns a
+`Range` whose indices are _exactly_ `start` and `end`, which is what
+`test_range_new` needs.
 
 ```flux
 impl Range {
+    #[contract]
+    #[spec(fn(start: i32, end: i32{start <= end}) -> Self[start, end])]
     pub fn new(start: i32, end: i32) -> Self {
         Range { start, end }
     }
@@ -423,6 +572,714 @@ fn test_union() {
 }
 ```
 
+== Constructors that Report Errors
+
+The constructor `new` for `Range` (once fixed) _requires_ valid inputs.
+As we did for `Positivei32`, lets also write a _smart_ constructor
+`try_new` that instead returns a `Result`, whose error says what went wrong.
+
+=== Errors for Ranges
+
+The error for `Range` carries the `start` and `end` that were
+rejected because `end < start`.
+
+```flux
+#[refined_by(start: int, end: int)]
+#[invariant(end < start)]
+#[derive(Debug)]
+pub struct InvalidRangeError {
+  #[field(i32[start])]
+  pub start: i32,
+  #[field(i32[end])]
+  pub end: i32,
+}
+
+impl std::fmt::Display for InvalidRangeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "range {}..{} ends before it starts", self.start, self.end)
+  }
+}
+
+impl std::error::Error for InvalidRangeError {}
+
+impl Range {
+  #[spec(fn(start: i32, end: i32)
+         -> Result<Range[start, end], InvalidRangeError[start, end]>[start <= end])]
+  pub fn try_new(start: i32, end: i32) -> Result<Self, InvalidRangeError> {
+    if start <= end {
+      Ok(Range { start, end })
+    } else {
+      Err(InvalidRangeError { start, end })
+    }
+  }
+}
+```
+
+=== Using the Constructors
+
+Callers can now `?` the errors, or, as Flux knows which variant is
+returned, `unwrap` the result when the input is known to be valid.
+
+```flux
+fn test_try_new() -> Result<i32, NonPositiveError> {
+  let p = Positivei32::try_new(99).unwrap();   // p: Positivei32[99]
+  assert(p.val == 99);
+  match Positivei32::try_new(-5) {
+    Ok(_) => assert(false),                     // unreachable
+    Err(e) => assert(e.val == -5),
+  }
+  let r = Range::try_new(15, 5);
+  assert(r.is_err());
+  let q = Positivei32::try_new(-1)?;           // returns the error
+  Ok(q.val)
+}
+```
+
+== A Range API
+
+Lets flesh out `Range` into a small library for _intervals_, where we think
+of a `Range` as the (half-open) set of integers `x` with `start <= x < end`,
+just like Rust's own `start..end`. Each method comes with a spec that
+describes its result in terms of the `start` and `end` of its inputs.
+
+=== Size and Membership
+
+The length of a `Range` from `i32::MIN` to `i32::MAX` does not fit in an
+`i32`, so `len` returns a `u32`, which it computes with `abs_diff`, whose
+spec (in @ch:externs) says that it returns the distance `abs(a - b)`.
+
+```flux
+impl Range {
+    #[spec(fn(&Self[@r]) -> u32[r.end - r.start])]
+    pub fn len(&self) -> u32 {
+        self.end.abs_diff(self.start)
+    }
+
+    #[spec(fn(&Self[@r]) -> bool[r.start == r.end])]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    #[spec(fn(&Self[@r], x: i32) -> bool[r.start <= x && x < r.end])]
+    pub fn contains(&self, x: i32) -> bool {
+        self.start <= x && x < self.end
+    }
+}
+```
+
+=== Intersection
+
+Two ranges _overlap_ when they have some element in common, that is, when
+the larger of the `start`s is less than the smaller of the `end`s. We
+describe this with a refinement function `overlaps`, and then say that
+`intersect` returns a valid `Option` _exactly_ when the ranges overlap.
+
+```flux
+defs! {
+    fn overlaps(r1: Range, r2: Range) -> bool {
+        max(r1.start, r2.start) < min(r1.end, r2.end)
+    }
+}
+
+impl Range {
+    #[spec(fn(&Self[@r1], &Self[@r2]) -> bool[overlaps(r1, r2)])]
+    pub fn overlaps(&self, other: &Range) -> bool {
+        let start = if self.start < other.start { other.start } else { self.start };
+        let end = if self.end < other.end { self.end } else { other.end };
+        start < end
+    }
+
+    #[spec(fn(&Self[@r1], &Self[@r2])
+              -> Option<Self[max(r1.start, r2.start), min(r1.end, r2.end)]>[overlaps(r1, r2)])]
+    pub fn intersect(&self, other: &Range) -> Option<Range> {
+        let start = if self.start < other.start { other.start } else { self.start };
+        let end = if self.end < other.end { self.end } else { other.end };
+        if start < end {
+            Some(Range { start, end })
+        } else {
+            None
+        }
+    }
+}
+```
+
+Note that we cannot (yet!) use the functions `min` and `max` from above
+in the _code_, as their specs are part of an exercise, so we just inline
+their definitions; Flux then checks that the `Range` in the `Some` is legal
+as `start < end`.
+
+=== Moving, Splitting and Clamping
+
+Finally, `shift` moves a range by `k`, as long as both ends stay within
+the `i32`s (using `in_i32` from @ch:externs), `split_at` cuts a range in two at
+some `k` _within_ the range, and `clamp` moves `x` to the nearest element
+of a _non-empty_ range, as described by the refinement function `clamp`
+from @ch:math.
+
+```flux
+impl Range {
+    #[spec(fn(&Self[@r], k: i32) -> Self[r.start + k, r.end + k]
+           requires in_i32(r.start + k) && in_i32(r.end + k))]
+    pub fn shift(&self, k: i32) -> Range {
+        Range { start: self.start + k, end: self.end + k }
+    }
+
+    #[contract]
+    #[spec(fn(&Self[@r], k: i32{r.start <= k && k <= r.end}) -> (Self[r.start, k], Self[k, r.end]))]
+    pub fn split_at(&self, k: i32) -> (Range, Range) {
+        (Range { start: self.start, end: k }, Range { start: k, end: self.end })
+    }
+
+    #[contract]
+    #[spec(fn(&Self[@r], x: i32) -> i32[clamp(x, r.start, r.end - 1)] requires r.start < r.end)]
+    pub fn clamp(&self, x: i32) -> i32 {
+        if x < self.start {
+            self.start
+        } else if self.end - 1 < x {
+            self.end - 1
+        } else {
+            x
+        }
+    }
+}
+```
+
+=== Converting to and from `std::ops::Range`
+
+Rust's `start..end` ranges are refined by their `start` and `end` too,
+so we can convert between them while keeping the indices. Going from a
+`std::ops::Range` to a `Range` may fail, as (unlike ours) Rust's ranges
+may have `start > end`.
+
+```flux
+impl From<Range> for std::ops::Range<i32> {
+    #[spec(fn(Range[@s, @e]) -> std::ops::Range<i32>[s, e])]
+    fn from(r: Range) -> Self {
+        r.start..r.end
+    }
+}
+
+impl Range {
+    #[spec(fn(std::ops::Range<i32>[@s, @e]) -> Option<Range[s, e]>[s <= e])]
+    pub fn from_std(r: std::ops::Range<i32>) -> Option<Range> {
+        if r.start <= r.end {
+            Some(Range { start: r.start, end: r.end })
+        } else {
+            None
+        }
+    }
+}
+```
+
+=== Using the Range API
+
+Here's a little test, where Flux uses the specs to check the `assert`s.
+
+```flux
+fn test_range_api() {
+  let r1 = Range { start: 0, end: 10 };
+  let r2 = Range { start: 5, end: 15 };
+  assert(r1.len() == 10);
+  assert(r1.contains(5) && !r1.contains(10));
+  let r3 = r1.intersect(&r2).unwrap();    // r3: Range[5, 10]
+  assert(r3.start == 5 && r3.end == 10);
+  assert(r1.intersect(&r2.shift(5)).is_none());
+  let (a, b) = r1.split_at(4);            // a: Range[0, 4], b: Range[4, 10]
+  assert(a.len() + b.len() == r1.len());
+  assert(r1.clamp(42) == 9);
+  let std_range: std::ops::Range<i32> = r1.into();
+  assert(Range::from_std(std_range).is_some());
+  assert(Range::from_std(r2.end..r2.start).is_none());
+}
+```
+
+== Sets of Ranges
+
+Next, lets use `Range` to build an `IntervalSet`: a set of integers
+represented as a _sorted_ list of ranges that neither overlap nor touch,
+for example, the free slots in a calendar or the free ports on a machine.
+Keeping the list in this canonical form is what makes the operations
+efficient: e.g. `contains` can stop as soon as it sees a range that starts
+after the element. So we'd like Flux to _check_ that every operation
+preserves the form.
+
+=== The Invariant
+
+An `RVec` only tracks its length, and so cannot describe how _neighboring_
+elements relate. Instead, we use a linked list, as a (recursive) `enum`, which
+we will see more of in @ch:05_enums. The index `lo` is the `start` of the
+_first_ range, or `2147483648` (one more than the largest `i32`) for
+the empty list.
+
+```flux
+#[refined_by(lo: int)]
+#[invariant(lo <= 2147483648)]
+pub enum IntervalSet {
+    #[variant(IntervalSet[2147483648])]
+    Nil,
+    #[variant(({Range[@s, @e] | s < e}, Box<IntervalSet{v: e < v}>) -> IntervalSet[s])]
+    Cons(Range, Box<IntervalSet>),
+}
+```
+
+The `variant` for `Cons` says that
+
+1. each range in the list is _non-empty_, as `s < e`, and
+2. the rest of the list must start _after_ the end `e` of the head range,
+   and, as ranges are half-open, must do so with a gap of at least one,
+   as `e < v`. Otherwise the two ranges would overlap or touch.
+
+The empty list's `lo` is larger than any `end`, so any range can go in front.
+
+=== Membership
+
+As the ranges are sorted, `contains` can stop as soon as it sees a range
+that starts after `x`.
+
+```flux
+impl IntervalSet {
+    #[spec(fn() -> IntervalSet[2147483648])]
+    pub fn new() -> Self {
+        IntervalSet::Nil
+    }
+
+    #[spec(fn(&IntervalSet[@lo], x: i32) -> bool{b: b => lo <= x})]
+    pub fn contains(&self, x: i32) -> bool {
+        match self {
+            IntervalSet::Nil => false,
+            IntervalSet::Cons(r, rest) => {
+                if x < r.start {
+                    false
+                } else if x < r.end {
+                    true
+                } else {
+                    rest.contains(x)
+                }
+            }
+        }
+    }
+}
+
+impl Default for IntervalSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+```
+
+=== Insertion
+
+To `insert` a (non-empty) range `r`, we walk down the list until we find
+where it goes. If `r` ends before the head `h` starts, it goes in front;
+if it starts after `h` ends, it goes somewhere in the rest of the list;
+otherwise `r` overlaps or touches `h`, so we merge the two and insert the
+merged range into the rest of the list, where it may swallow up more ranges.
+
+```flux
+impl IntervalSet {
+    #[spec(fn(IntervalSet[@lo], r: {Range[@s, @e] | s < e}) -> IntervalSet[min(lo, s)])]
+    pub fn insert(self, r: Range) -> IntervalSet {
+        match self {
+            IntervalSet::Nil => IntervalSet::Cons(r, Box::new(IntervalSet::Nil)),
+            IntervalSet::Cons(h, rest) => {
+                if r.end < h.start {
+                    IntervalSet::Cons(r, Box::new(IntervalSet::Cons(h, rest)))
+                } else if h.end < r.start {
+                    IntervalSet::Cons(h, Box::new(rest.insert(r)))
+                } else {
+                    let start = if h.start < r.start { h.start } else { r.start };
+                    let end = if h.end < r.end { r.end } else { h.end };
+                    rest.insert(Range { start, end })
+                }
+            }
+        }
+    }
+}
+```
+
+The spec says that the new list starts at the smaller of the old `lo` and
+the start `s` of `r`, which is exactly the fact Flux needs, in the second
+case, to check that `rest.insert(r)` can go after `h`: both `rest` and `r`
+start after the end of `h`.
+
+=== Removal
+
+To `remove` the elements of `r`, we cut out the part of each range that
+overlaps `r`, which leaves at most two pieces: the part _before_ `r`,
+and the part _after_ `r`.
+
+```flux
+impl IntervalSet {
+    #[spec(fn(IntervalSet[@lo], r: {Range[@s, @e] | s < e}) -> IntervalSet{v: lo <= v})]
+    pub fn remove(self, r: Range) -> IntervalSet {
+        match self {
+            IntervalSet::Nil => IntervalSet::Nil,
+            IntervalSet::Cons(h, rest) => {
+                if r.end <= h.start {
+                    IntervalSet::Cons(h, rest)
+                } else if h.end <= r.start {
+                    IntervalSet::Cons(h, Box::new(rest.remove(r)))
+                } else {
+                    let (s, e) = (r.start, r.end);
+                    let rest = rest.remove(r);
+                    let rest = if e < h.end {
+                        IntervalSet::Cons(Range { start: e, end: h.end }, Box::new(rest))
+                    } else {
+                        rest
+                    };
+                    if h.start < s {
+                        IntervalSet::Cons(Range { start: h.start, end: s }, Box::new(rest))
+                    } else {
+                        rest
+                    }
+                }
+            }
+        }
+    }
+}
+```
+
+This time, the spec only says that the list starts no earlier than before,
+which is enough for Flux to check that the pieces (and what remains of the
+rest of the list) are still sorted and apart.
+
+=== Union
+
+Finally, the `union` of two sets inserts the ranges of `other`, one at
+a time, into `self`.
+
+```flux
+impl IntervalSet {
+    #[spec(fn(IntervalSet[@a], IntervalSet[@b]) -> IntervalSet[min(a, b)])]
+    pub fn union(self, other: IntervalSet) -> IntervalSet {
+        match other {
+            IntervalSet::Nil => self,
+            IntervalSet::Cons(r, rest) => self.insert(r).union(*rest),
+        }
+    }
+}
+```
+
+#alert("success", [
+*EXERCISE:* The `spec` for `contains` only says that the result is `false`
+for elements smaller than `lo`. Can you write an `is_empty` method, and use
+the index `lo` to give it a _precise_ spec?
+])
+
+=== Using Interval Sets
+
+Here are the free slots in a calendar, with meetings booked from `5` to `25`.
+
+```flux
+fn test_interval_set() {
+  let free = IntervalSet::new()
+      .insert(Range { start: 0, end: 10 })
+      .insert(Range { start: 20, end: 30 })
+      .insert(Range { start: 10, end: 15 });     // {0..15, 20..30}
+  let booked = Range { start: 5, end: 25 };
+  let free = free.remove(booked);                // {0..5, 25..30}
+  let later = IntervalSet::new().insert(Range { start: 40, end: 50 });
+  let free = free.union(later);                  // {0..5, 25..30, 40..50}
+  let empty = IntervalSet::new().insert(Range { start: 1, end: 1 }); // rejected!
+}
+```
+
+== Bounded Integers
+
+The `Positivei32` type, and the `s + k <= 100` constraint in `add_points`
+from @ch:02_refinements:combining-indexes-and-constraints, are both ways of
+keeping an integer within a range. Rather than write a new `struct` for each
+range, we can use _const generics_ (which we will see more of in @ch:07_consts)
+to write a single type that is parameterized by the lower and upper bounds.
+
+```flux
+#[refined_by(v: int)]
+#[invariant(LO <= v && v <= HI)]
+pub struct Bounded<const LO: i64, const HI: i64> {
+    #[field(i64[v])]
+    val: i64,
+}
+```
+
+As with `Positivei32`, the index `v` tracks the value of the `val` field,
+and the `invariant` says that `v` is always between `LO` and `HI`.
+So, for example, a `Bounded<0, 100>` is an integer between `0` and `100`.
+
+=== Creating and Reading Bounded Integers
+
+The smart constructor `try_from` returns a valid `Option`
+_exactly when_ the input is in range.
+
+```flux
+impl<const LO: i64, const HI: i64> Bounded<LO, HI> {
+    #[spec(fn(x: i64) -> Option<Self[x]>[LO <= x && x <= HI])]
+    pub fn try_from(x: i64) -> Option<Self> {
+        if LO <= x && x <= HI {
+            Some(Bounded { val: x })
+        } else {
+            None
+        }
+    }
+
+    #[spec(fn(&Self[@b]) -> i64[b])]
+    pub fn get(&self) -> i64 {
+        self.val
+    }
+}
+```
+
+=== Arithmetic on Bounded Integers
+
+Adding `k` to a bounded integer may take it out of range. There are three
+ways to deal with that, which differ in their specs:
+
+- `checked_add` returns a valid `Option` exactly when `v + k` is in range,
+- `saturating_add` clamps the sum to the range, using the refinement
+  functions `min` and `max` we defined above, and
+- `wrapping_add` wraps the sum around to the start of the range,
+  for any step `k`, while `wrapping_add_once` only takes steps `k` of
+  at most `HI - LO`.
+
+The sum `v + k` itself may not fit in an `i64`, so `checked_add` and
+`saturating_add` first add with the `i64` methods of the same name, whose
+specs we will see in @ch:externs, and only then check the bounds.
+
+```flux
+impl<const LO: i64, const HI: i64> Bounded<LO, HI> {
+    #[spec(fn(&Self[@v], k: i64) -> Option<Self[v + k]>[LO <= v + k && v + k <= HI])]
+    pub fn checked_add(&self, k: i64) -> Option<Self> {
+        match self.val.checked_add(k) {
+            Some(sum) => Self::try_from(sum),
+            None => None,
+        }
+    }
+
+    #[spec(fn(&Self[@v], k: i64) -> Self[max(LO, min(HI, v + k))])]
+    pub fn saturating_add(&self, k: i64) -> Self {
+        let sum = self.val.saturating_add(k);
+        let val = if sum < LO { LO } else if HI < sum { HI } else { sum };
+        Bounded { val }
+    }
+
+    #[spec(fn(&Self[@v], k: i64{0 <= k && k <= HI - LO})
+           -> Self[if v + k <= HI { v + k } else { v + k - (HI - LO + 1) }]
+           requires HI - LO <= 9223372036854775807)]
+    pub fn wrapping_add_once(&self, k: i64) -> Self {
+        let room = HI - self.val;
+        if k <= room {
+            Bounded { val: self.val + k }
+        } else {
+            Bounded { val: LO + (k - room - 1) }
+        }
+    }
+
+    #[trusted]
+    #[spec(fn(&Self[@v], k: i64) -> Self[wrap(v + k, LO, HI)]
+           requires HI - LO <= 9223372036854775807)]
+    pub fn wrapping_add(&self, k: i64) -> Self {
+        let width = HI as i128 - LO as i128 + 1;
+        self.wrapping_add_once((k as i128).rem_euclid(width) as i64)
+    }
+}
+```
+
+#alert("info", [
+*Wrapping Once:* Wrapping by an arbitrary `k` needs a `%` by `HI - LO + 1`,
+which is not a constant. Reasoning about such _non-linear_ arithmetic is
+undecidable in general, so we split the work in two. `wrapping_add_once` only
+takes steps `k` of at most `HI - LO`, which wrap around at most _once_. Its
+spec, and its code, only need `+` and `-`, and Flux checks it without any
+`#[trusted]`. `wrapping_add` then takes _any_ step `k`: it first reduces `k`
+with `rem_euclid` to a step of at most `HI - LO`, which is the one fact about
+`%` that Flux cannot check, so only `wrapping_add` is `#[trusted]`. Its spec
+uses the `wrap` function from @ch:externs. The precondition
+`HI - LO <= 9223372036854775807` says that the width of the range fits in an
+`i64`, so that `HI - self.val` cannot overflow.
+])
+
+=== Using Bounded Integers
+
+We can now define as many ranges as we like, for example, to
+revisit the scores from `add_points` or to validate port numbers.
+
+```flux
+type Score = Bounded<0, 100>;
+type Port = Bounded<1, 65535>;
+
+#[spec(fn(&Score[@s], k: i64) -> Option<Score[s + k]>[s + k <= 100 && 0 <= s + k])]
+fn add_points(s: &Score, k: i64) -> Option<Score> {
+    s.checked_add(k)
+}
+
+fn test_bounded() {
+    let s = Score::try_from(90).unwrap();   // s: Score[90]
+    let t = s.saturating_add(30);           // t: Score[100]
+    assert(t.get() == 100);
+    let u = s.wrapping_add(11);             // u: Score[0]
+    let w = s.wrapping_add(-91);            // w: Score[100]
+    assert(add_points(&s, 10).is_some());
+    assert(add_points(&s, 11).is_none());
+    let p = Port::try_from(0).unwrap();     // rejected!
+}
+```
+
+== Counters and Budgets
+
+In @ch:03_ownership we saw that an `invariant` like `0 <= v` on a plain
+`&mut i32` is not enough to verify `test_incr_inv`: we need the _strong_
+update that says exactly how the value changes. Lets package up two common
+patterns as `struct`s whose methods come with such strong updates.
+
+=== Monotonic Counters
+
+A `MonotonicCounter` is a counter whose value can only go _up_.
+
+```flux
+#[refined_by(n: int)]
+#[invariant(0 <= n)]
+pub struct MonotonicCounter {
+    #[field(u64[n])]
+    val: u64,
+}
+
+impl MonotonicCounter {
+    #[spec(fn() -> MonotonicCounter[0])]
+    pub fn new() -> Self {
+        MonotonicCounter { val: 0 }
+    }
+
+    #[spec(fn(&MonotonicCounter[@n]) -> u64[n])]
+    pub fn get(&self) -> u64 {
+        self.val
+    }
+
+    #[spec(fn(self: &mut MonotonicCounter[@n]) requires in_u64(n + 1)
+           ensures self: MonotonicCounter[n + 1])]
+    pub fn incr(&mut self) {
+        self.val += 1;
+    }
+
+    #[spec(fn(self: &mut MonotonicCounter[@n], k: u64) requires in_u64(n + k)
+           ensures self: MonotonicCounter[n + k])]
+    pub fn advance(&mut self, k: u64) {
+        self.val += k;
+    }
+
+    #[spec(fn(self: &mut MonotonicCounter[@n], m: u64) ensures self: MonotonicCounter[max(n, m)])]
+    pub fn observe(&mut self, m: u64) {
+        if self.val < m {
+            self.val = m;
+        }
+    }
+}
+
+impl Default for MonotonicCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+```
+
+The `ensures` clause of _every_ method that takes `&mut self` says the new
+value is at least the old one: `incr` and `advance` add to the value, and
+`observe` raises the value to `m` if it is larger, which is handy to keep
+track of, e.g. the latest timestamp seen so far. As the field `val` is private,
+code outside this module can only change a counter via these methods, and
+so can never make it go down. Of course, a `u64` cannot go up forever, so
+`incr` and `advance` _require_ that the new value still fits, using `in_u64`
+from @ch:externs; otherwise, the addition could overflow and wrap around to
+a _smaller_ value.
+
+Callers can compose these specs into their own. For example, `tick_all`
+promises that both counters go up, as long as `a` has room to go up by one.
+
+```flux
+#[spec(fn(a: &mut MonotonicCounter[@m], b: &mut MonotonicCounter[@n])
+       requires in_u64(m + 1)
+       ensures a: MonotonicCounter{v: m <= v}, b: MonotonicCounter{v: n <= v})]
+fn tick_all(a: &mut MonotonicCounter, b: &mut MonotonicCounter) {
+    a.incr();
+    b.observe(a.get());
+}
+```
+
+=== Budgets
+
+A `Budget` tracks how much of some resource is `remaining`. We can only
+`spend(k)` when `k <= remaining`, much like `add_points` in
+@ch:02_refinements:combining-indexes-and-constraints could only add `k`
+points when the total stayed within `100`.
+
+```flux
+#[refined_by(remaining: int)]
+#[invariant(0 <= remaining)]
+pub struct Budget {
+    #[field(u64[remaining])]
+    remaining: u64,
+}
+
+impl Budget {
+    #[spec(fn(total: u64) -> Budget[total])]
+    pub fn new(total: u64) -> Self {
+        Budget { remaining: total }
+    }
+
+    #[spec(fn(&Budget[@r]) -> u64[r])]
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    #[contract(r = self.remaining)]
+    #[spec(fn(self: &mut Budget[@r], k: u64{k <= r}) ensures self: Budget[r - k])]
+    pub fn spend(&mut self, k: u64) {
+        self.remaining -= k;
+    }
+
+    #[spec(fn(self: &mut Budget[@r], k: u64) -> bool[k <= r]
+           ensures self: Budget[if k <= r { r - k } else { r }])]
+    pub fn try_spend(&mut self, k: u64) -> bool {
+        if k <= self.remaining {
+            self.spend(k);
+            true
+        } else {
+            false
+        }
+    }
+}
+```
+
+The `try_spend` method lets us spend when we do not _know_ statically that
+there is enough left: it returns `true` exactly when it spent `k`, and
+leaves the budget unchanged otherwise.
+
+=== Rate Limiting
+
+Together, the two types give us a simple rate limiter that counts the
+requests `served` while spending them from a `budget`. The spec for `serve`
+is stated purely in terms of the specs of `advance` and `spend`, and so
+inherits the preconditions of both.
+
+```flux
+#[spec(fn(served: &mut MonotonicCounter[@n], budget: &mut Budget[@r], k: u64{k <= r})
+       requires in_u64(n + k)
+       ensures served: MonotonicCounter[n + k], budget: Budget[r - k])]
+fn serve(served: &mut MonotonicCounter, budget: &mut Budget, k: u64) {
+    budget.spend(k);
+    served.advance(k);
+}
+
+fn test_rate_limit() {
+    let mut served = MonotonicCounter::new();
+    let mut budget = Budget::new(10);
+    serve(&mut served, &mut budget, 4);
+    serve(&mut served, &mut budget, 6);
+    assert(served.get() == 10);
+    assert(budget.remaining() == 0);
+    assert(!budget.try_spend(1));
+    serve(&mut served, &mut budget, 1); // rejected!
+}
+```
+
 == Summary
 
 To conclude, we saw how you can use Flux to refine
@@ -430,9 +1287,120 @@ user-defined `struct` to
 
 1. *track* at the type-level, the values of fields, and
 2. *constrain* the sets of _legal_ values for those structs.
+
+The module in @ch:numeric packages up `Positivei32` and its
+relatives, like `NonZero` and `Percentage`, for every integer width.
 //
 To see a more entertaining example, check out
 #link("https://github.com/flux-rs/flux/blob/f200714dfae5e7c9a3bdf7231191499f56aac45b/tests/tests/pos/surface/date.rs")[this code]
 that shows how we can use refinements to
 ensure that only _legal_ `Date`s can be
 constructed at compile time!
+```flux
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::props::*;
+    use rand::Rng;
+    use std::collections::BTreeSet;
+
+    /// The ranges of `set`, checking that they are non-empty, sorted and apart.
+    fn ranges(set: &IntervalSet) -> Vec<(i32, i32)> {
+        let mut res: Vec<(i32, i32)> = vec![];
+        let mut set = set;
+        while let IntervalSet::Cons(r, rest) = set {
+            assert!(r.start < r.end);
+            if let Some((_, end)) = res.last() {
+                assert!(*end < r.start);
+            }
+            res.push((r.start, r.end));
+            set = rest;
+        }
+        res
+    }
+
+    /// Applies `ops` to an `IntervalSet` and to a `BTreeSet` model: a positive
+    /// `(s, n)` inserts `s..s+n`, a negative one removes `-s..-s+n`.
+    fn run(ops: &[(i64, i64)]) -> bool {
+        let mut set = IntervalSet::new();
+        let mut model = BTreeSet::new();
+        for &(s, n) in ops {
+            let (s, n) = (s as i32, n as i32 + 1);
+            let r = Range { start: s.abs(), end: s.abs() + n };
+            let xs = s.abs()..s.abs() + n;
+            if 0 <= s {
+                set = set.insert(r);
+                model.extend(xs);
+            } else {
+                set = set.remove(r);
+                xs.for_each(|x| {
+                    model.remove(&x);
+                });
+            }
+            let covered: BTreeSet<i32> = ranges(&set).into_iter().flat_map(|(s, e)| s..e).collect();
+            if covered != model || !(-5..120).all(|x| set.contains(x) == model.contains(&x)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Compares the `Bounded` arithmetic against `i128` arithmetic,
+    /// for sums that may overflow an `i64`.
+    fn bounded_ops<const LO: i64, const HI: i64>(v: i64, k: i64) -> bool {
+        let b = Bounded::<LO, HI>::try_from(v).unwrap();
+        let (lo, hi, sum) = (LO as i128, HI as i128, v as i128 + k as i128);
+        let checked = b.checked_add(k).map(|c| c.get() as i128);
+        let saturated = b.saturating_add(k).get() as i128;
+        let wrapped = lo + (sum - lo).rem_euclid(hi - lo + 1);
+        let once = 0 <= k && (k as i128) <= hi - lo;
+        (checked == Some(sum).filter(|s| lo <= *s && *s <= hi))
+            && saturated == sum.clamp(lo, hi)
+            && b.wrapping_add(k).get() as i128 == wrapped
+            && (!once || b.wrapping_add_once(k).get() as i128 == wrapped)
+    }
+
+    #[test]
+    fn prop_bounded_add() {
+        let extremes = [i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX - 1, i64::MAX];
+        for_all(
+            "bounded_add",
+            |rng| {
+                let pick = |rng: &mut rand::rngs::StdRng| match rng.gen_bool(0.5) {
+                    true => extremes[rng.gen_range(0..extremes.len())],
+                    false => rng.gen_range(i64::MIN..=i64::MAX),
+                };
+                (pick(rng), pick(rng))
+            },
+            |p| shrink_pair(p, shrink_i64, shrink_i64),
+            |_| true,
+            |&(v, k)| {
+                bounded_ops::<-50, 50>(v.rem_euclid(101) - 50, k)
+                    && bounded_ops::<0, { i64::MAX }>(v.max(0), k)
+                    && bounded_ops::<{ i64::MIN }, -1>(v.min(-1), k)
+            },
+        );
+    }
+
+    #[test]
+    fn range_len_at_extremes() {
+        assert_eq!(Range { start: i32::MIN, end: i32::MAX }.len(), u32::MAX);
+        assert_eq!(Range { start: -1, end: -1 }.len(), 0);
+        assert_eq!(Range { start: i32::MIN, end: 0 }.shift(i32::MAX).len(), 2147483648);
+    }
+
+    #[test]
+    fn prop_interval_set() {
+        for_all(
+            "interval_set",
+            |rng| {
+                let n = rng.gen_range(0..30);
+                (0..n).map(|_| (rng.gen_range(-100..100), rng.gen_range(0..10))).collect::<Vec<_>>()
+            },
+            |ops: &Vec<(i64, i64)>| shrink_vec(ops, |op| shrink_pair(op, shrink_i64, shrink_i64)),
+            |ops| ops.iter().all(|&(_, n)| 0 <= n),
+            |ops| run(ops),
+        );
+    }
+}
+```
@@ -7,6 +7,8 @@
 extern crate flux_rs;
 use flux_rs::attrs::*;
 use flux_rs::assert;
+use crate::ch03_structs::Positivei32;
+use crate::ch05_vectors::RVec;
 ```
 
 Previously in @ch:04_structs we saw how to refine structs to constrain the space
@@ -257,33 +259,192 @@ much like the `x < 12` branch is dead code in the
 
 // <!-- SLIDE -->
 
-== Using `unwrap`
+=== Combinators <ch:05_enums:combinators>
 
-Next, lets see some examples of how to _use_ refined options
-to safely `unwrap`.
+Of course, we rarely `match` on an `Option` by hand; instead we use
+_combinators_ like `map` and `and_then`. Their specs say how the
+`valid` index of the output relates to those of the inputs, so Flux
+does not lose track of the validity of a chain of operations.
+
+- `map` applies a function to the payload, so it _preserves_ `valid`;
+- `and_then` and `filter` can only produce a `Some` from a `Some`;
+- `or` is valid if either input is, and `zip` only if both are;
+- `take` and `replace` return the old value and leave `None`
+  (respectively, `Some`) in its place.
+
+```flux
+impl<T> Option<T> {
+  #[spec(fn(Self[@b], f: F) -> Option<U>[b])]
+  pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Option<U> {
+    match self {
+      Option::Some(v) => Option::Some(f(v)),
+      Option::None => Option::None,
+    }
+  }
+
+  #[spec(fn(Self[@b], f: F) -> Option<U>{v: v => b})]
+  pub fn and_then<U, F: FnOnce(T) -> Option<U>>(self, f: F) -> Option<U> {
+    match self {
+      Option::Some(v) => f(v),
+      Option::None => Option::None,
+    }
+  }
+
+  #[spec(fn(Self[@b1], Self[@b2]) -> Self[b1 || b2])]
+  pub fn or(self, other: Self) -> Self {
+    match self {
+      Option::Some(v) => Option::Some(v),
+      Option::None => other,
+    }
+  }
+
+  #[spec(fn(Self, default: T) -> T)]
+  pub fn unwrap_or(self, default: T) -> T {
+    match self {
+      Option::Some(v) => v,
+      Option::None => default,
+    }
+  }
+
+  #[spec(fn(Self[@b], p: P) -> Self{v: v => b})]
+  pub fn filter<P: FnOnce(&T) -> bool>(self, p: P) -> Self {
+    match self {
+      Option::Some(v) => if p(&v) { Option::Some(v) } else { Option::None },
+      Option::None => Option::None,
+    }
+  }
+
+  #[spec(fn(Self[@b1], Option<U>[@b2]) -> Option<(T, U)>[b1 && b2])]
+  pub fn zip<U>(self, other: Option<U>) -> Option<(T, U)> {
+    match (self, other) {
+      (Option::Some(a), Option::Some(b)) => Option::Some((a, b)),
+      _ => Option::None,
+    }
+  }
+
+  #[spec(fn(self: &mut Self[@b]) -> Self[b] ensures self: Self[false])]
+  pub fn take(&mut self) -> Self {
+    std::mem::replace(self, Option::None)
+  }
+
+  #[spec(fn(self: &mut Self[@b], value: T) -> Self[b] ensures self: Self[true])]
+  pub fn replace(&mut self, value: T) -> Self {
+    std::mem::replace(self, Option::Some(value))
+  }
+}
+```
+
+Now we can `unwrap` at the end of a chain, as long as each step
+keeps the option valid.
+
+```flux
+fn test_combinators() {
+  let a = Option::Some(1).map(|x| x + 1).unwrap();
+  let b = Option::None.or(Option::Some(2)).unwrap();
+  let (c, d) = Option::Some(3).zip(Option::Some(true)).unwrap();
+  assert(!Option::Some(4).zip(Option::<bool>::None).is_some());
+  assert(Option::<i32>::None.unwrap_or(5) == 5);
+
+  let mut o = Option::Some(6);
+  let e = o.take().unwrap();      // `o` is now `None`
+  assert(!o.is_some());
+  let f = o.replace(7);           // `o` is now `Some(7)`
+  assert(!f.is_some());
+  let g = o.unwrap();
+
+  let h = Option::Some(8).filter(|x| *x > 10);
+  h.unwrap();                     // rejected!
+}
+```
+
+#alert("success", [
+*EXERCISE:* Why does Flux reject the last `unwrap` in `test_combinators`?
+Which other combinator could you use on `h` to get an `i32` without
+any risk of a panic?
+])
 
 // <!-- SLIDE -->
 
+== Failure with a Reason: A Refined Result
+
+Sometimes, an `Option` is not enough: when an operation fails, we want
+to know _why_. Rust's `Result` carries a payload in _both_ variants,
+and we can refine it exactly like we did `Option`.
+
+=== Refining Results <ch:05_enums:refined-result>
+
+We index `Result` by a `bool` that tracks whether the value is `Ok`.
+
+```flux
+#[refined_by(ok: bool)]
+pub enum Result<T, E> {
+    #[variant((T) -> Result<T, E>[{ok: true}])]
+    Ok(T),
+    #[variant((E) -> Result<T, E>[{ok: false}])]
+    Err(E),
+}
+```
+
+Just like for `Option`, pattern matching tells Flux the value of `ok`,
+so we can precisely specify the methods that query, or safely
+`unwrap`, a `Result`.
+
+```flux
+impl<T, E> Result<T, E> {
+  #[spec(fn(&Self[@ok]) -> bool[ok])]
+  pub fn is_ok(&self) -> bool {
+    match self {
+      Result::Ok(_) => true,
+      Result::Err(_) => false,
+    }
+  }
+
+  #[spec(fn(&Self[@ok]) -> bool[!ok])]
+  pub fn is_err(&self) -> bool {
+    match self {
+      Result::Ok(_) => false,
+      Result::Err(_) => true,
+    }
+  }
+
+  #[spec(fn(Self[true]) -> T)]
+  pub fn unwrap(self) -> T {
+    match self {
+      Result::Ok(v) => v,
+      Result::Err(_) => unreachable(),
+    }
+  }
+
+  #[spec(fn(Self[false]) -> E)]
+  pub fn unwrap_err(self) -> E {
+    match self {
+      Result::Ok(_) => unreachable(),
+      Result::Err(e) => e,
+    }
+  }
+}
+```
+
 === Safe Division
 
-Here's a safe divide-by-zero function that returns an `Option<i32>`
+Now we can write a safe divide-by-zero function that returns a `Result`,
+whose `Err` explains the failure, and whose `spec` says _exactly_ when it
+fails: when the divisor is not positive. Otherwise, the `Ok` payload is the
+quotient, as described by `trunc_div` from @ch:externs, as Rust's `/`
+truncates towards zero.
 
 ```flux
-#[spec(fn(n:i32, k:i32) -> Option<i32>)]
-pub fn safe_divide(n: i32, k: i32) -> Option<i32> {
+#[spec(fn(n: i32, k: i32) -> Result<i32[trunc_div(n, k)], &'static str>[0 < k])]
+pub fn safe_divide(n: i32, k: i32) -> Result<i32, &'static str> {
   if k > 0 {
-    Option::Some(n / k)
+    Result::Ok(n / k)
   } else {
-    Option::None
+    Result::Err("divisor is not positive")
   }
 }
 ```
 
-#alert("success", [
-*EXERCISE:* Why does the test below fail to type check?
-Can you fix the `spec` for `safe_divide` so Flux is happy
-with `test_safe_divide`?
-])
+So Flux lets us `unwrap` the result of a division by a positive number.
 
 ```flux
 fn test_safe_divide() -> i32 {
@@ -291,48 +452,160 @@ fn test_safe_divide() -> i32 {
 }
 ```
 
-// <!-- SLIDE -->
-
 === Smart Constructors Revisited
 
 Recall the `struct Positivei32` from @ch:04_structs:positive-integers
-and the smart constructor we wrote for it.
+and the smart constructor `try_new` we wrote for it, which returns a
+`Result` whose error carries the rejected value. That is the _standard_
+`Result`, rather than the one we defined above, but as we will see in
+@ch:externs, Flux refines it in just the same way, and the `spec`
+
+```rust
+#[spec(fn(val: i32) -> Result<Positivei32[val], NonPositiveError[val]>[0 < val])]
+```
+
+of `try_new` says _exactly_ which variant it returns for which input.
+So Flux knows that the `unwrap` below cannot fail.
+
+#alert("success", [
+*EXERCISE:* Change the `10` below to `0`. Why does Flux complain,
+and where? What if you drop the `[0 < val]` from the `spec` of `try_new`?
+])
 
 ```flux
-#[refined_by(n: int)]
-#[invariant(n > 0)]
-struct Positivei32 {
-    #[field(i32[n])]
-    val: i32
+fn test_new_unwrap() {
+    Positivei32::try_new(10).unwrap();
 }
+```
+
+=== Transforming Results
+
+The combinators let us work on the payload without matching.
+`map` and `map_err` transform one of the payloads, and so _preserve_
+the index. `and_then` runs a fallible function on the `Ok` payload,
+so the output can only be `Ok` if the input was, but not conversely,
+as the function itself may fail.
+Finally, `ok` and `err` convert a `Result` into our refined `Option`
+from @ch:05_enums:refined-option, which is valid exactly when the
+corresponding variant was present.
 
-impl Positivei32 {
-  #[spec(fn(val: i32) -> Option<Self>)]
-  pub fn new(val: i32) -> Option<Self> {
-    if val > 0 {
-      Option::Some(Positivei32 { val })
-    } else {
-      Option::None
+```flux
+impl<T, E> Result<T, E> {
+  #[spec(fn(Self[@ok], f: F) -> Result<U, E>[ok])]
+  pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Result<U, E> {
+    match self {
+      Result::Ok(v) => Result::Ok(f(v)),
+      Result::Err(e) => Result::Err(e),
+    }
+  }
+
+  #[spec(fn(Self[@ok], f: F) -> Result<T, D>[ok])]
+  pub fn map_err<D, F: FnOnce(E) -> D>(self, f: F) -> Result<T, D> {
+    match self {
+      Result::Ok(v) => Result::Ok(v),
+      Result::Err(e) => Result::Err(f(e)),
+    }
+  }
+
+  #[spec(fn(Self[@ok], f: F) -> Result<U, E>{v: v => ok})]
+  pub fn and_then<U, F: FnOnce(T) -> Result<U, E>>(self, f: F) -> Result<U, E> {
+    match self {
+      Result::Ok(v) => f(v),
+      Result::Err(e) => Result::Err(e),
+    }
+  }
+
+  #[spec(fn(Self[@ok]) -> Option<T>[ok])]
+  pub fn ok(self) -> Option<T> {
+    match self {
+      Result::Ok(v) => Option::Some(v),
+      Result::Err(_) => Option::None,
+    }
+  }
+
+  #[spec(fn(Self[@ok]) -> Option<E>[!ok])]
+  pub fn err(self) -> Option<E> {
+    match self {
+      Result::Ok(_) => Option::None,
+      Result::Err(e) => Option::Some(e),
     }
   }
 }
 ```
 
-#alert("success", [
-*EXERCISE:* The code below has a function that
-invokes the smart constructor and then `unwrap`s
-the result. Why is Flux complaining? Can you fix
-the `spec` of `new` so that the `test_unwrap` figure
-out how to fix the `spec` of `new` so that `test_new_unwrap`
-is accepted?
-])
+=== Propagating Errors
+
+Rust's `?` operator is defined via the (unstable) `Try` trait, so it
+does not work with our custom `Result`. Instead, `propagate` re-types
+an `Err` so it can be returned from a function with a different `Ok`
+type, and the `try_ok!` macro mimics `?` by unwrapping an `Ok` or
+returning the `Err`. As Flux knows which branch is taken, the `ok`
+index flows through these early returns too. Finally, `into_std`
+converts to a `std::result::Result`, whose extern spec in @ch:externs
+has the same `bool` index, for code that wants to use `?` directly.
 
 ```flux
-fn test_new_unwrap() {
-    Positivei32::new(10).unwrap();
+impl<T, E> Result<T, E> {
+  #[spec(fn(Self[false]) -> Result<U, E>[false])]
+  pub fn propagate<U>(self) -> Result<U, E> {
+    match self {
+      Result::Ok(_) => unreachable(),
+      Result::Err(e) => Result::Err(e),
+    }
+  }
+
+  #[spec(fn(Self[@ok]) -> std::result::Result<T, E>[ok])]
+  pub fn into_std(self) -> std::result::Result<T, E> {
+    match self {
+      Result::Ok(v) => Ok(v),
+      Result::Err(e) => Err(e),
+    }
+  }
+}
+
+macro_rules! try_ok {
+  ($e:expr) => {
+    match $e {
+      Result::Ok(v) => v,
+      Result::Err(e) => return Result::Err(e),
+    }
+  };
+}
+```
+
+=== Precise, Composable Contracts
+
+As fallible functions say _exactly_ when they fail, we can compose them.
+For example, here is a function that uses `safe_divide` to divide twice,
+which fails if _either_ division does.
+
+```flux
+#[spec(fn(n: i32, a: i32, b: i32) -> Result<i32, &'static str>[0 < a && 0 < b])]
+pub fn divide_twice(n: i32, a: i32, b: i32) -> Result<i32, &'static str> {
+  let q = try_ok!(safe_divide(n, a));
+  safe_divide(q, b)
+}
+
+fn test_result() {
+  let q = safe_divide(10, 2).unwrap();              // q: i32[5]
+  assert(q == 5);
+  assert(safe_divide(10, 0).is_err());
+  let e = safe_divide(10, 0).unwrap_err();
+  let r = safe_divide(10, 2).map(|q| q + 1);        // still `Ok`
+  assert(r.unwrap() == 6);
+  let s = divide_twice(100, 5, 2).ok().unwrap();    // valid `Option`
+  assert(divide_twice(100, 0, 2).err().is_some());
+  let t = safe_divide(10, 2).and_then(|q| safe_divide(q, 0));
+  assert(t.is_err());                                // rejected!
 }
 ```
 
+#alert("success", [
+*EXERCISE:* Why does Flux reject the last `assert` in `test_result`?
+How would you rewrite the computation of `t` without `and_then`,
+so that Flux accepts the `assert`?
+])
+
 // <!-- SLIDE -->
 
 == TypeStates: A Refined Timer
@@ -473,15 +746,434 @@ fn test_tick() {
 }
 ```
 
+// <!-- SLIDE -->
+
+== A Timer State Machine <ch:05_enums:timer-state-machine>
+
+The `Timer` above has just two states. A job scheduler needs more:
+a timer may be _running_, it may be _paused_ (and later resumed), or it
+may have _expired_. Lets see how to use refinements to check the rules
+for moving between these states.
+
+=== Refined States
+
+We refine the `JobTimer` by the `remaining` time and a `bool` that
+says whether the timer is `running`. The indices determine the state:
+
+- `Running(n)` is `JobTimer[n, true]`,
+- `Paused(n)` is `JobTimer[n, false]`, and
+- `Expired` is `JobTimer[0, false]`.
+
+The `variant` attributes require that `Running` and `Paused` timers
+have some time _left_, so a timer has expired _exactly_ when its
+`remaining` time is `0`.
+
+```flux
+#[refined_by(remaining: int, running: bool)]
+#[invariant(0 <= remaining && (running => 0 < remaining))]
+#[derive(Clone, Copy)]
+pub enum JobTimer {
+    #[variant(({usize[@n] | 0 < n}) -> JobTimer[n, true])]
+    Running(usize),
+
+    #[variant(({usize[@n] | 0 < n}) -> JobTimer[n, false])]
+    Paused(usize),
+
+    #[variant(JobTimer[0, false])]
+    Expired,
+}
+```
+
+=== Starting and Observing Timers
+
+A timer `start`s out running, unless there is no time left, in which
+case it has already expired. The observers tell us the indices.
+
+```flux
+impl JobTimer {
+    #[spec(fn(n: usize) -> JobTimer[n, 0 < n])]
+    pub fn start(n: usize) -> Self {
+        if n > 0 {
+            JobTimer::Running(n)
+        } else {
+            JobTimer::Expired
+        }
+    }
+
+    #[spec(fn(&JobTimer[@n, @r]) -> usize[n])]
+    pub fn remaining(&self) -> usize {
+        match self {
+            JobTimer::Running(n) => *n,
+            JobTimer::Paused(n) => *n,
+            JobTimer::Expired => 0,
+        }
+    }
+
+    #[spec(fn(&JobTimer[@n, @r]) -> bool[r])]
+    pub fn is_running(&self) -> bool {
+        matches!(self, JobTimer::Running(_))
+    }
+
+    #[spec(fn(&JobTimer[@n, @r]) -> bool[n == 0])]
+    pub fn is_expired(&self) -> bool {
+        matches!(self, JobTimer::Expired)
+    }
+}
+```
+
+=== Transitions
+
+Next, the events that change the state. The `spec`s say which states
+each event may be applied in, and how it updates the indices.
+
+- `tick` may happen in any state, but only a `running` timer counts
+  down, and it _expires_ exactly when the remaining time reaches `0`;
+- `pause` is only allowed on a `running` timer;
+- `resume` is only allowed on a paused timer, i.e. one that is not
+  `running` but has time left; and
+- `reset(n)` is allowed from any state, and (re)starts the timer with `n`.
+
+```flux
+impl JobTimer {
+    #[spec(fn(self: &mut JobTimer[@n, @r])
+           ensures self: JobTimer[if r { n - 1 } else { n }, r && 1 < n])]
+    pub fn tick(&mut self) {
+        if let JobTimer::Running(n) = *self {
+            *self = if n > 1 { JobTimer::Running(n - 1) } else { JobTimer::Expired };
+        }
+    }
+
+    #[spec(fn(self: &mut JobTimer[@n, true]) ensures self: JobTimer[n, false])]
+    pub fn pause(&mut self) {
+        match *self {
+            JobTimer::Running(n) => *self = JobTimer::Paused(n),
+            JobTimer::Paused(_) | JobTimer::Expired => unreachable(),
+        }
+    }
+
+    #[spec(fn(self: &mut JobTimer[@n, false]) requires 0 < n ensures self: JobTimer[n, true])]
+    pub fn resume(&mut self) {
+        match *self {
+            JobTimer::Paused(n) => *self = JobTimer::Running(n),
+            JobTimer::Running(_) | JobTimer::Expired => unreachable(),
+        }
+    }
+
+    #[spec(fn(self: &mut JobTimer, n: usize) ensures self: JobTimer[n, 0 < n])]
+    pub fn reset(&mut self, n: usize) {
+        *self = JobTimer::start(n);
+    }
+}
+```
+
+=== Checking the Transitions
+
+Flux now tracks the state of the timer through a sequence of events,
+and rejects the events that are not allowed.
+
+```flux
+fn test_job_timer() {
+  let mut t = JobTimer::start(2);   // t: JobTimer[2, true]
+  t.pause();                        // t: JobTimer[2, false]
+  t.tick();                         // paused, so still JobTimer[2, false]
+  assert(t.remaining() == 2);
+  t.resume();                       // t: JobTimer[2, true]
+  t.tick();                         // t: JobTimer[1, true]
+  t.tick();                         // t: JobTimer[0, false]
+  assert(t.is_expired());
+  t.reset(3);                       // t: JobTimer[3, true]
+  assert(t.is_running());
+  t.resume();                       // rejected: already running
+}
+```
+
+#alert("success", [
+*EXERCISE:* Calling `resume` on an expired timer is rejected too.
+Why? Write an event `extend(k)` that adds `k` seconds to a running
+or paused timer, without changing whether it is `running`.
+What is its `spec`?
+])
+
+// <!-- SLIDE -->
+
+== A Timer Wheel <ch:05_enums:timer-wheel>
+
+An event loop juggles _many_ timers at once. Lets build a `TimerWheel`
+that keeps a collection of `JobTimer`s in an `RVec` (@ch:06_vectors),
+ticks them all together, and hands out _handles_ that are used to refer
+to (e.g. to `cancel`) a particular timer.
+
+=== Handles and Wheels
+
+A `TimerHandle` is just the position of a timer in the wheel, which we
+track in its index. Its field is private, so the only way to get a handle
+is from the wheel itself.
+
+```flux
+#[refined_by(idx: int)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimerHandle {
+    #[field(usize[idx])]
+    idx: usize,
+}
+```
+
+Like the `RingBuf` from @ch:06_vectors, the `TimerWheel` is refined by
+its `size`, the number of slots it holds, which is also stored in a
+run-time field. As `JobTimer`s are `Copy`, each timer is stored by value.
+When a timer fires, or is cancelled, its slot is put on a list of
+`free_slots`, so that it can be reused by a later timer. The wheel is
+also refined by the number of `free` slots, and the `field` of the list
+says that every free slot is in bounds.
+
+```flux
+#[refined_by(size: int, free: int)]
+pub struct TimerWheel {
+    #[field(RVec<JobTimer>[size])]
+    timers: RVec<JobTimer>,
+    #[field(usize[size])]
+    size: usize,
+    #[field(RVec<TimerHandle{h: h < size}>[free])]
+    free_slots: RVec<TimerHandle>,
+    #[field(usize[free])]
+    free: usize,
+}
+```
+
+=== Scheduling and Cancelling
+
+We `schedule` a timer to expire after some (positive) `delay`. If there
+is a free slot, we reuse it, and otherwise we append the timer to the
+wheel, so its handle is the old `size`. Either way, the `spec` tells us
+that the handle is in bounds of the _new_ wheel, which never shrinks,
+and so a handle stays in bounds for the lifetime of the wheel. (Of course,
+once its timer has fired, the handle may refer to a _newer_ timer that
+reuses the slot.) To `cancel` a timer, we require a handle that is in
+bounds, and mark the timer as `Expired`, freeing its slot if the timer
+was still pending, which is what the result says.
+
+```flux
+impl TimerWheel {
+    #[spec(fn() -> TimerWheel[0, 0])]
+    pub fn new() -> Self {
+        TimerWheel { timers: RVec::new(), size: 0, free_slots: RVec::new(), free: 0 }
+    }
+
+    #[spec(fn(&TimerWheel[@n, @f]) -> usize[n])]
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    #[spec(fn(&TimerWheel[@n, @f]) -> bool[n == 0])]
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    #[spec(fn(self: &mut TimerWheel[@n, @f], delay: usize{0 < delay})
+              -> TimerHandle{h: if 0 < f { h < n } else { h == n }}
+           ensures self: TimerWheel[if 0 < f { n } else { n + 1 }, if 0 < f { f - 1 } else { 0 }])]
+    pub fn schedule(&mut self, delay: usize) -> TimerHandle {
+        if self.free > 0 {
+            let handle = self.free_slots.pop();
+            self.free -= 1;
+            self.timers[handle.idx] = JobTimer::start(delay);
+            handle
+        } else {
+            let handle = TimerHandle { idx: self.size };
+            self.timers.push(JobTimer::start(delay));
+            self.size += 1;
+            handle
+        }
+    }
+
+    #[spec(fn(self: &mut TimerWheel[@n, @f], h: TimerHandle{h < n}) -> bool
+           ensures self: TimerWheel{w: w.size == n})]
+    pub fn cancel(&mut self, h: TimerHandle) -> bool {
+        let pending = !self.timers[h.idx].is_expired();
+        if pending {
+            self.timers[h.idx] = JobTimer::Expired;
+            self.free_slots.push(h);
+            self.free += 1;
+        }
+        pending
+    }
+
+    #[spec(fn(&TimerWheel[@n, @f], h: TimerHandle{h < n}) -> usize)]
+    pub fn remaining(&self, h: TimerHandle) -> usize {
+        self.timers[h.idx].remaining()
+    }
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+```
+
+=== Advancing Time
+
+Finally, `advance(k)` ticks every timer `k` times, and returns the handles
+of the timers that expired along the way, in the order in which they
+expired. The `spec` says that all of these handles are in bounds, so the
+caller can use them, e.g. to look up the job that was waiting on the timer.
+
+Rather than tick every timer once per tick, which takes time proportional
+to `k` times the `size` of the wheel, `advance` skips the ticks at which
+nothing happens. It finds the `next` tick at which some running timer
+expires (or the end of the `k` ticks), and then moves all the running
+timers `next` ticks forward at once.
+
+```flux
+impl TimerWheel {
+    #[spec(fn(self: &mut TimerWheel[@n, @f], k: usize) -> RVec<TimerHandle{h: h < n}>
+           ensures self: TimerWheel{w: w.size == n})]
+    pub fn advance(&mut self, k: usize) -> RVec<TimerHandle> {
+        let mut expired = RVec::new();
+        let mut t = 0;
+        while t < k {
+            let mut next = k - t;
+            let mut i = 0;
+            while i < self.size {
+                if let JobTimer::Running(r) = self.timers[i] && r < next {
+                    next = r;
+                }
+                i += 1;
+            }
+            let mut i = 0;
+            while i < self.size {
+                if let JobTimer::Running(r) = self.timers[i] {
+                    if r <= next {
+                        let handle = TimerHandle { idx: i };
+                        self.timers[i] = JobTimer::Expired;
+                        self.free_slots.push(handle);
+                        self.free += 1;
+                        expired.push(handle);
+                    } else {
+                        self.timers[i] = JobTimer::Running(r - next);
+                    }
+                }
+                i += 1;
+            }
+            t += next;
+        }
+        expired
+    }
+}
+```
+
+=== Using the Wheel
+
+Flux checks that we only use handles that came from (a large enough)
+wheel, and that the handles returned by `advance` can be used right away.
+
+```flux
+fn test_timer_wheel() {
+  let mut wheel = TimerWheel::new();     // wheel: TimerWheel[0, 0]
+  let a = wheel.schedule(2);             // a: TimerHandle[0]
+  let b = wheel.schedule(5);             // b: TimerHandle[1]
+  let fired = wheel.advance(3);          // a expires, and its slot is freed
+  let first = fired[0];                  // rejected: `fired` may be empty
+  wheel.cancel(b);                       // ok, as 1 < 2
+  let c = wheel.schedule(4);             // c: a reused slot, or TimerHandle[2]
+  wheel.cancel(c);                       // ok, either way
+  let bad = TimerHandle { idx: 3 };
+  wheel.cancel(bad);                     // rejected: out of bounds
+}
+```
+
+#alert("success", [
+*EXERCISE:* Once a timer has fired, its handle may refer to a newer timer
+that reuses the slot, so cancelling with a _stale_ handle cancels the
+wrong timer. Can you add a _generation_ to the slots and handles so that
+`cancel` with a stale handle does nothing? What should its `spec` say?
+])
+
 == Summary
 
 In this chapter, we saw how you refine an `enum` with indices, and then specify
 the values of the indices for each `variant`. This let us, for example, determine
-whether an `Option` is `Some` or `None` at compile time, and to safely `unwrap`
-the former, and to encode a "typestate" mechanism for a `Timer` that ensures we
-only `deactivate` when the timer has expired. You can do other fun things, like
+whether an `Option` is `Some` or `None` (or a `Result` is `Ok` or `Err`) at compile
+time, and to safely `unwrap` the former, and to encode a "typestate" mechanism for
+a `Timer` that ensures we only `deactivate` when the timer has expired, and to check
+the transitions of a `JobTimer` between its running, paused and expired states,
+and that the handles of a `TimerWheel` are always in bounds. You can do other fun things, like
 
 - track the #link("https://github.com/flux-rs/flux/blob/main/tests/tests/pos/enums/list00.rs")[length] of a linked list,
 - track the #link("https://github.com/flux-rs/flux/blob/main/tests/tests/pos/enums/list01.rs")[set of elements] in the list, or
 - track the set of permissions that a user is granted (@ch:12_sets), or
 - ensure the layers of a neural network are composed correctly (@ch:14_neural).
+```flux
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::props::SEED;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::option::Option; // not the refined `Option` above
+
+    /// Drives a `TimerWheel` through thousands of random (but seeded)
+    /// `schedule`, `cancel` and `advance` events on a virtual clock, and
+    /// checks each `advance` against a model that stores the deadline of
+    /// the timer in each slot, if it is pending, and that slots are reused.
+    #[test]
+    fn sim_timer_wheel() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let mut wheel = TimerWheel::new();
+        let mut deadlines: Vec<Option<usize>> = vec![];
+        let mut now = 0;
+        while now < 5000 {
+            match rng.gen_range(0..10) {
+                0..=3 => {
+                    let delay = rng.gen_range(1..50);
+                    let h = wheel.schedule(delay);
+                    if deadlines.contains(&None) {
+                        assert!(deadlines[h.idx].is_none());
+                        deadlines[h.idx] = Some(now + delay);
+                    } else {
+                        assert_eq!(h.idx, deadlines.len());
+                        deadlines.push(Some(now + delay));
+                    }
+                }
+                4 if !deadlines.is_empty() => {
+                    let i = rng.gen_range(0..deadlines.len());
+                    let pending = deadlines[i].is_some();
+                    assert_eq!(wheel.cancel(TimerHandle { idx: i }), pending);
+                    deadlines[i] = None;
+                }
+                _ => {
+                    let k = rng.gen_range(1..5);
+                    let mut expected: Vec<(usize, usize)> = deadlines
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, d)| d.filter(|d| *d <= now + k).map(|d| (d, i)))
+                        .collect();
+                    expected.sort();
+                    let fired: Vec<usize> = Vec::from(wheel.advance(k)).iter().map(|h| h.idx).collect();
+                    assert_eq!(fired, expected.iter().map(|(_, i)| *i).collect::<Vec<_>>());
+                    for (_, i) in expected {
+                        deadlines[i] = None;
+                    }
+                    now += k;
+                }
+            }
+            assert_eq!(wheel.len(), deadlines.len());
+            for (i, d) in deadlines.iter().enumerate() {
+                assert_eq!(wheel.remaining(TimerHandle { idx: i }), d.map_or(0, |d| d - now));
+            }
+        }
+    }
+
+    /// `advance` only visits the ticks at which some timer expires, so
+    /// it can jump (far) ahead in time.
+    #[test]
+    fn advance_skips_empty_ticks() {
+        let mut wheel = TimerWheel::default();
+        let a = wheel.schedule(1 << 40);
+        let b = wheel.schedule(3);
+        let fired: Vec<TimerHandle> = Vec::from(wheel.advance(1 << 50));
+        assert_eq!(fired, vec![b, a]);
+        assert_eq!(wheel.schedule(7), a);
+    }
+}
+```
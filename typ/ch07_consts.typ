@@ -2,6 +2,12 @@
 
 = Const Generics <ch:07_consts>
 
+```flux
+#![allow(unused)]
+extern crate flux_rs;
+use flux_rs::attrs::*;
+```
+
 // [Online demo](https://flux.goto.ucsd.edu/index.html#?demo=arrays.rs)
 
 Rust has a built-in notion of _arrays_: collections of objects of
@@ -97,8 +103,17 @@ We can fix these errors by requiring that the input
 - `i` be a valid color index, i.e. `i < 3` and
 - `pixels` be non-empty, i.e. have size `n` where `0 < n`
 
-```rust
+```flux
+pub type Pixel = [u8; 3]; // RGB values
+
 #[spec(fn(pixels: &[Pixel][@n], i:usize{i<3}) -> u64 requires 0 < n)]
+pub fn average_color(pixels: &[Pixel], i: usize) -> u64 {
+    let mut sum = 0;
+    for p in pixels {
+        sum += p[i] as u64;
+    }
+    sum / pixels.len() as u64
+}
 ```
 
 #figure(
@@ -188,9 +203,10 @@ fn dot_k<const N:usize>(x: [f32;N], y: [f32;N], k: usize) -> f32 {
 
 #strong[The Strict] approach is to require that `k` be less than or equal to `N`
 
-```rust
+```flux
+#[contract]
 #[sig(fn(x: [f32;N], y: [f32;N], k:usize{k <= N}) -> f32)]
-fn dot_k<const N:usize>(x: [f32;N], y: [f32;N], k: usize) -> f32 {
+pub fn dot_k<const N:usize>(x: [f32;N], y: [f32;N], k: usize) -> f32 {
     let mut sum = 0.0;
     for i in 0..k {
         sum += x[i] * y[i];
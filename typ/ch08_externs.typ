@@ -283,10 +283,13 @@ so that Flux verifies `test_unwrap`?
 === A Safe Division Function
 
 Lets write a safe-division function, that checks if the divisor
-is non-zero before doing the division.
+is non-zero before doing the division. The `spec` says that the payload
+of a `Some` is the quotient, which the `#[contract]` from @ch:contracts
+also checks at run time.
 
 ```flux
-#[spec(fn (num: u32, denom: u32) -> Option<u32[num / denom]>)]
+#[contract]
+#[spec(fn (num: u32, denom: u32) -> Option<u32{v: v == num / denom}>)]
 pub fn safe_div(num: u32, denom: u32) -> Option<u32> {
     if denom == 0 {
         None
@@ -311,6 +314,16 @@ pub fn test_safe_div() {
 ```
 
 
+=== Specs for Combinators
+
+We can likewise give extern specs to the `Option` combinators, that
+relate the `valid` index of the output to those of the inputs, exactly
+as for the refined `Option` in @ch:05_enums:combinators. You can find
+the finished specs, for `map`, `and_then`, `or`, `unwrap_or`, `filter`,
+`zip`, `take` and `replace`, together with the above specs for the type
+definition, in @ch:externs, where Flux checks them along with the code
+that uses them.
+
 == Extern Specs for Structs: `Vec`
 
 Previously, in @ch:06_vectors, we saw how to define
@@ -516,6 +529,29 @@ fn pop2<T>(vec: &mut Vec<T>) -> (T, T)  {
 }
 ```
 
+== Extern Specs for Integer Methods
+
+Back in @ch:02_refinements and @ch:05_enums we blithely ignored the possibility
+of arithmetic _overflow_. The standard library offers a family of methods that
+deal with overflow explicitly, by returning an `Option` (`checked_*`), clamping
+the result (`saturating_*`), or wrapping around (`wrapping_*`). With
+`extern_spec` we can say _exactly_ what each of these methods returns.
+For example, `checked_add` returns a valid `Option` exactly when the sum
+fits in an `i32`, and then, the payload _is_ the sum:
+
+```rust
+#[extern_spec]
+impl i32 {
+    #[spec(fn(a: i32, b: i32) -> Option<i32[a + b]>[in_i32(a + b)])]
+    const fn checked_add(self, rhs: i32) -> Option<i32>;
+}
+```
+
+where `in_i32(x)` says that `x` is between `i32::MIN` and `i32::MAX`.
+The specs for the whole family, for each of the common integer types,
+and an overflow-free version of `factorial` that uses them,
+are in @ch:externs.
+
 == Summary
 
 Previously, we saw how to attach refined specifications for
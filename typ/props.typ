@@ -0,0 +1,247 @@
+#import "../orly-modified.typ": alert
+
+= Property Tests <ch:props>
+
+Flux checks `spec`s statically, but `#[trusted]` code (like the `RVec` API
+in @ch:06_vectors) is taken on faith. As a fallback, this module lets us
+_test_ a `spec` by
+
+1. drawing random inputs that satisfy the function's _precondition_,
+2. calling the function, and
+3. checking that its output satisfies the _postcondition_.
+
+When a test fails, the failing input is _shrunk_ to a minimal counterexample,
+which makes it much easier to figure out what went wrong. The random number
+generator uses a fixed seed, so failures are reproducible.
+
+The pre- and postconditions are not copied into the tests by hand, where they
+could drift out of sync with the `spec`, but _derived_ from the `spec` itself:
+in test builds, the `#[contract]` attribute of @ch:contracts also generates,
+for each function `f` it is placed on, the predicates `f_requires` and
+`f_ensures`. They take the same parameters as `f` (and `f_ensures` also takes
+its result), and say whether _all_ the clauses that `#[contract]` checks hold.
+The tests only spell out what the `spec` cannot, for example the `ensures`
+clauses that say how a `&mut` parameter changes.
+
+```flux
+use flux_rs::attrs::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+
+/// The number of random inputs drawn for each property.
+pub const CASES: usize = 256;
+
+/// The seed for the random number generator.
+pub const SEED: u64 = 0x5eed;
+
+/// Checks that `prop` holds for `CASES` inputs drawn by `draw`, which should
+/// only produce inputs that satisfy the precondition `pre`. On failure, panics
+/// with the minimal counterexample found by repeatedly applying `shrink`.
+pub fn for_all<T, G, S, Pre, P>(name: &str, mut draw: G, shrink: S, pre: Pre, prop: P)
+where
+    T: Clone + Debug,
+    G: FnMut(&mut StdRng) -> T,
+    S: Fn(&T) -> Vec<T>,
+    Pre: Fn(&T) -> bool,
+    P: Fn(&T) -> bool,
+{
+    if let Some(input) = counterexample(&mut draw, &shrink, &pre, &prop) {
+        panic!("property `{name}` failed, minimal counterexample: {input:?}");
+    }
+}
+
+/// Returns the minimal input that falsifies `prop`, if any of the `CASES`
+/// inputs drawn by `draw` does so.
+pub fn counterexample<T, G, S, Pre, P>(draw: &mut G, shrink: &S, pre: &Pre, prop: &P) -> Option<T>
+where
+    T: Clone + Debug,
+    G: FnMut(&mut StdRng) -> T,
+    S: Fn(&T) -> Vec<T>,
+    Pre: Fn(&T) -> bool,
+    P: Fn(&T) -> bool,
+{
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for _ in 0..CASES {
+        let input = draw(&mut rng);
+        assert!(pre(&input), "generator violated precondition: {input:?}");
+        if !holds(prop, &input) {
+            return Some(minimize(input, shrink, pre, prop));
+        }
+    }
+    None
+}
+
+/// Greedily replaces `input` with the first of its shrinks that still
+/// satisfies the precondition but falsifies `prop`, until there are none.
+fn minimize<T, S, Pre, P>(mut input: T, shrink: &S, pre: &Pre, prop: &P) -> T
+where
+    S: Fn(&T) -> Vec<T>,
+    Pre: Fn(&T) -> bool,
+    P: Fn(&T) -> bool,
+{
+    while let Some(smaller) = shrink(&input)
+        .into_iter()
+        .find(|x| pre(x) && !holds(prop, x))
+    {
+        input = smaller;
+    }
+    input
+}
+
+/// A property fails if it returns `false` _or_ panics.
+fn holds<T, P: Fn(&T) -> bool>(prop: &P, input: &T) -> bool {
+    panic::catch_unwind(AssertUnwindSafe(|| prop(input))).unwrap_or(false)
+}
+```
+
+== Shrinking
+
+The shrinkers return "smaller" candidates for a value: integers move towards
+`0`, and vectors drop elements or shrink them.
+
+```flux
+/// Candidates for a smaller integer: `0`, half of `x`, and one step towards `0`.
+pub fn shrink_i64(x: &i64) -> Vec<i64> {
+    let x = *x;
+    let mut res = vec![];
+    if x != 0 {
+        res.push(0);
+        res.push(x / 2);
+        res.push(x - x.signum());
+    }
+    res.dedup();
+    res
+}
+
+/// Candidates for a smaller `usize`: `0`, half of `x`, and `x - 1`.
+pub fn shrink_usize(x: &usize) -> Vec<usize> {
+    shrink_i64(&(*x as i64)).into_iter().map(|y| y as usize).collect()
+}
+
+/// Candidates for a smaller pair, shrinking one component at a time.
+pub fn shrink_pair<A: Clone, B: Clone>(
+    (a, b): &(A, B),
+    shrink_a: impl Fn(&A) -> Vec<A>,
+    shrink_b: impl Fn(&B) -> Vec<B>,
+) -> Vec<(A, B)> {
+    let mut res: Vec<(A, B)> = shrink_a(a).into_iter().map(|a| (a, b.clone())).collect();
+    res.extend(shrink_b(b).into_iter().map(|b| (a.clone(), b)));
+    res
+}
+
+/// Candidates for a smaller vector: drop the back half, drop a single element,
+/// or shrink a single element.
+pub fn shrink_vec<T: Clone>(xs: &[T], shrink: impl Fn(&T) -> Vec<T>) -> Vec<Vec<T>> {
+    let mut res = vec![];
+    if xs.len() > 1 {
+        res.push(xs[..xs.len() / 2].to_vec());
+    }
+    for i in 0..xs.len() {
+        let mut ys = xs.to_vec();
+        ys.remove(i);
+        res.push(ys);
+    }
+    for i in 0..xs.len() {
+        for x in shrink(&xs[i]) {
+            let mut ys = xs.to_vec();
+            ys[i] = x;
+            res.push(ys);
+        }
+    }
+    res
+}
+```
+
+== Generators
+
+Finally, a generator for vectors whose size is drawn from a given range,
+which lets us generate, e.g., the _non-empty_ vectors required by `RVec::pop`.
+
+```flux
+/// A random vector whose size is in `lo..hi` and whose elements are in `-100..100`.
+pub fn gen_vec(rng: &mut StdRng, lo: usize, hi: usize) -> Vec<i64> {
+    let n = rng.gen_range(lo..hi);
+    (0..n).map(|_| rng.gen_range(-100..100)).collect()
+}
+```
+
+== Example: `dot_k`
+
+Lets test the _strict_ version of `dot_k` from @ch:07_consts, which
+_requires_ that `k <= N`. The generator only draws `k` in `0..=N`, which
+`dot_k_requires` confirms, and the property checks that the result is the
+sum of the first `k` products, which also checks that `dot_k` does not panic
+with an out-of-bounds access.
+The elements are small integers, so the `f32` sums are exact.
+
+```flux
+/// A random array whose elements are small integers.
+pub fn gen_array<const N: usize>(rng: &mut StdRng) -> [f32; N] {
+    std::array::from_fn(|_| rng.gen_range(-10..10) as f32)
+}
+```
+
+Each chapter's `tests` module uses the same recipe for its own functions,
+for example, `add_points` in @ch:02_refinements and the `RVec` API in
+@ch:06_vectors, which we can run with `cargo test`.
+
+```flux
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ch06_consts::{dot_k, dot_k_requires};
+
+    #[test]
+    fn shrinks_to_minimal_counterexample() {
+        let mut draw = |rng: &mut StdRng| rng.gen_range(0..1000usize);
+        let res = counterexample(&mut draw, &shrink_usize, &|_| true, &|x: &usize| *x < 50);
+        assert_eq!(res, Some(50));
+    }
+
+    #[test]
+    fn shrinks_vectors() {
+        let mut draw = |rng: &mut StdRng| gen_vec(rng, 0, 20);
+        let shrink = |xs: &Vec<i64>| shrink_vec(xs, shrink_i64);
+        let res = counterexample(&mut draw, &shrink, &|_| true, &|xs: &Vec<i64>| {
+            xs.iter().all(|x| *x < 90)
+        });
+        assert_eq!(res, Some(vec![90]));
+    }
+
+    #[test]
+    fn panics_are_failures() {
+        let mut draw = |rng: &mut StdRng| rng.gen_range(0..10usize);
+        let res = counterexample(&mut draw, &shrink_usize, &|_| true, &|x: &usize| 10 / x > 0);
+        assert_eq!(res, Some(0));
+    }
+
+    #[test]
+    fn prop_dot_k() {
+        for_all(
+            "dot_k",
+            |rng| (gen_array::<8>(rng), gen_array::<8>(rng), rng.gen_range(0..=8)),
+            |(x, y, k): &([f32; 8], [f32; 8], usize)| {
+                shrink_usize(k).into_iter().map(|k| (*x, *y, k)).collect()
+            },
+            |(x, y, k)| dot_k_requires(*x, *y, *k),
+            |(x, y, k)| dot_k(*x, *y, *k) == (0..*k).map(|i| x[i] * y[i]).sum::<f32>(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "minimal counterexample")]
+    fn detects_out_of_bounds() {
+        // without the precondition, `dot_k` panics, and the
+        // counterexample shrinks down to the smallest bad `k`
+        for_all(
+            "dot_k",
+            |rng| rng.gen_range(0..=16),
+            shrink_usize,
+            |_| true,
+            |k: &usize| dot_k([1.0; 8], [1.0; 8], *k) == *k as f32,
+        );
+    }
+}
+```